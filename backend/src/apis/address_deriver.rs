@@ -0,0 +1,117 @@
+use ethers::{
+    types::{Bytes, Signature, H256},
+    utils::keccak256,
+};
+use std::error::Error;
+
+use super::chain_config::{find_chain_config, AddressFormat, ChainConfig};
+
+/// Derives the address that signed a proof of address ownership, following the address scheme of a specific chain.
+pub trait AddressDeriver {
+    /// Returns the address that produced `signature` over `message`
+    fn derive_address(&self, signature: &Bytes, message: &Bytes) -> Result<String, Box<dyn Error>>;
+}
+
+/// Keccak-based address derivation used by Ethereum and EVM compatible chains
+pub struct EthereumAddressDeriver {
+    message_prefix: String,
+}
+
+impl EthereumAddressDeriver {
+    pub fn new(message_prefix: &str) -> Self {
+        EthereumAddressDeriver {
+            message_prefix: message_prefix.to_string(),
+        }
+    }
+
+    /// Returns the digest signed by the exchange: the keccak256 hash of the ABI encoded message, prefixed as an EIP-191 personal message with the message prefix of the chain
+    pub fn signed_digest(&self, message: &Bytes) -> H256 {
+        let message_hash = keccak256(message);
+
+        let mut prefixed_message = self.message_prefix.as_bytes().to_vec();
+        prefixed_message.extend_from_slice(message_hash.len().to_string().as_bytes());
+        prefixed_message.extend_from_slice(&message_hash);
+
+        H256::from(keccak256(prefixed_message))
+    }
+}
+
+impl Default for EthereumAddressDeriver {
+    fn default() -> Self {
+        EthereumAddressDeriver::new(&ChainConfig::ethereum().message_prefix)
+    }
+}
+
+impl AddressDeriver for EthereumAddressDeriver {
+    fn derive_address(&self, signature: &Bytes, message: &Bytes) -> Result<String, Box<dyn Error>> {
+        let signature = Signature::try_from(signature.as_ref())?;
+        let address = signature.recover(self.signed_digest(message))?;
+
+        Ok(format!("{:?}", address))
+    }
+}
+
+/// Blake2b-based address derivation, e.g. for Polkadot. Not supported yet.
+pub struct Blake2bAddressDeriver;
+
+impl AddressDeriver for Blake2bAddressDeriver {
+    fn derive_address(
+        &self,
+        _signature: &Bytes,
+        _message: &Bytes,
+    ) -> Result<String, Box<dyn Error>> {
+        Err("Blake2b address derivation is not supported yet".into())
+    }
+}
+
+/// Returns the address deriver matching the `chain` column of the signature CSV, according to the configuration of the chain
+pub fn address_deriver_for_chain(
+    chain: &str,
+    chain_configs: &[ChainConfig],
+) -> Result<Box<dyn AddressDeriver>, Box<dyn Error>> {
+    let chain_config = find_chain_config(chain_configs, chain)?;
+
+    match chain_config.address_format {
+        AddressFormat::Evm => Ok(Box::new(EthereumAddressDeriver::new(
+            &chain_config.message_prefix,
+        ))),
+        AddressFormat::Blake2b => Ok(Box::new(Blake2bAddressDeriver)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::csv_parser::parse_signature_csv;
+
+    #[test]
+    fn test_derive_ethereum_address() {
+        let address_ownership_proofs = parse_signature_csv("../csv/signatures.csv").unwrap();
+        let chain_configs = vec![ChainConfig::ethereum()];
+
+        for proof in address_ownership_proofs {
+            let deriver = address_deriver_for_chain(&proof.chain, &chain_configs).unwrap();
+            let address = deriver
+                .derive_address(&proof.signature, &proof.message)
+                .unwrap();
+
+            assert!(address.eq_ignore_ascii_case(&proof.cex_address));
+        }
+    }
+
+    #[test]
+    fn test_unsupported_chains() {
+        let proof = &parse_signature_csv("../csv/signatures.csv").unwrap()[0];
+        let chain_configs = vec![
+            ChainConfig::ethereum(),
+            ChainConfig::new("DOT", 0, "", AddressFormat::Blake2b),
+        ];
+
+        let deriver = address_deriver_for_chain("DOT", &chain_configs).unwrap();
+        assert!(deriver
+            .derive_address(&proof.signature, &proof.message)
+            .is_err());
+
+        assert!(address_deriver_for_chain("BTC", &chain_configs).is_err());
+    }
+}
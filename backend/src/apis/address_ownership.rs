@@ -1,22 +1,72 @@
 use crate::contracts::{generated::summa_contract::AddressOwnershipProof, signer::SummaSigner};
 use std::{error::Error, result::Result};
 
-use super::csv_parser::parse_signature_csv;
+use super::{
+    address_deriver::address_deriver_for_chain, chain_config::ChainConfig,
+    csv_parser::parse_signature_csv,
+};
 
 pub struct AddressOwnership<'a> {
     address_ownership_proofs: Vec<AddressOwnershipProof>,
+    chain_configs: Vec<ChainConfig>,
     signer: &'a SummaSigner,
 }
 
+/// Verifies that every signature was produced by the claimed `cex_address`, using the configuration of its chain
+pub fn verify_address_ownership_proofs(
+    address_ownership_proofs: &[AddressOwnershipProof],
+    chain_configs: &[ChainConfig],
+) -> Result<(), Box<dyn Error>> {
+    for proof in address_ownership_proofs {
+        let deriver = address_deriver_for_chain(&proof.chain, chain_configs)?;
+        let derived_address = deriver.derive_address(&proof.signature, &proof.message)?;
+
+        if !derived_address.eq_ignore_ascii_case(&proof.cex_address) {
+            return Err(format!(
+                "Signature for {} address {} was produced by {}",
+                proof.chain, proof.cex_address, derived_address
+            )
+            .into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the indices of the proofs whose signature wasn't produced by the claimed `cex_address`, or can't be checked, e.g. because its chain isn't configured
+pub fn invalid_address_ownership_proofs(
+    address_ownership_proofs: &[AddressOwnershipProof],
+    chain_configs: &[ChainConfig],
+) -> Vec<usize> {
+    address_ownership_proofs
+        .iter()
+        .enumerate()
+        .filter(|(_, proof)| {
+            verify_address_ownership_proofs(std::slice::from_ref(*proof), chain_configs).is_err()
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
 impl AddressOwnership<'_> {
     pub fn new<'a>(
         signer: &'a SummaSigner,
         signature_csv_path: &str,
+    ) -> Result<AddressOwnership<'a>, Box<dyn Error>> {
+        Self::new_with_chain_configs(signer, signature_csv_path, vec![ChainConfig::default()])
+    }
+
+    /// Same as `new`, with the configurations of the chains the signatures may belong to
+    pub fn new_with_chain_configs<'a>(
+        signer: &'a SummaSigner,
+        signature_csv_path: &str,
+        chain_configs: Vec<ChainConfig>,
     ) -> Result<AddressOwnership<'a>, Box<dyn Error>> {
         let address_ownership_proofs = parse_signature_csv(signature_csv_path)?;
 
         Ok(AddressOwnership {
             address_ownership_proofs,
+            chain_configs,
             signer,
         })
     }
@@ -25,10 +75,35 @@ impl AddressOwnership<'_> {
         &self.address_ownership_proofs
     }
 
+    pub fn get_chain_configs(&self) -> &Vec<ChainConfig> {
+        &self.chain_configs
+    }
+
+    /// Verifies that every signature was produced by the claimed `cex_address`, using the configuration of its chain
+    pub fn verify_address_ownership(&self) -> Result<(), Box<dyn Error>> {
+        verify_address_ownership_proofs(&self.address_ownership_proofs, &self.chain_configs)
+    }
+
+    /// Checks every signature without touching the chain, e.g. before spending gas on `dispatch_proof_of_address_ownership`.
+    ///
+    /// Returns the indices of the invalid signatures, counting the rows of the signature CSV from 0 and excluding the header.
+    pub fn verify_all_offline(&self) -> Result<(), Vec<usize>> {
+        let invalid_indices =
+            invalid_address_ownership_proofs(&self.address_ownership_proofs, &self.chain_configs);
+
+        if invalid_indices.is_empty() {
+            Ok(())
+        } else {
+            Err(invalid_indices)
+        }
+    }
+
     // This function dispatches the proof of address ownership. Before calling this function,
     // ensure externally that the provided `addresses` in `address_ownership_proof` are not already registered
     // on the Summa contract.
     pub async fn dispatch_proof_of_address_ownership(&mut self) -> Result<(), Box<dyn Error>> {
+        self.verify_address_ownership()?;
+
         self.signer
             .submit_proof_of_address_ownership(self.address_ownership_proofs.clone())
             .await?;
@@ -36,3 +111,73 @@ impl AddressOwnership<'_> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::{address_deriver::EthereumAddressDeriver, chain_config::AddressFormat};
+    use ethers::{
+        abi::AbiEncode,
+        signers::{LocalWallet, Signer},
+        types::Bytes,
+        utils::to_checksum,
+    };
+    use std::str::FromStr;
+
+    #[test]
+    fn test_address_ownership_with_custom_chain_config() {
+        let testnet = ChainConfig::new(
+            "SEP",
+            11155111,
+            "\x19Summa Testnet Signed Message:\n",
+            AddressFormat::Evm,
+        );
+
+        let wallet = LocalWallet::from_str(
+            "0x59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d",
+        )
+        .unwrap();
+        let message: Bytes = "Summa proof of solvency for CryptoExchange"
+            .to_string()
+            .encode()
+            .into();
+
+        // The exchange signs the message prefixed with the message prefix of the testnet
+        let digest = EthereumAddressDeriver::new(&testnet.message_prefix).signed_digest(&message);
+        let signature = wallet.sign_hash(digest).unwrap();
+
+        let proofs = vec![AddressOwnershipProof {
+            chain: testnet.name.clone(),
+            cex_address: to_checksum(&wallet.address(), None),
+            signature: signature.to_vec().into(),
+            message,
+        }];
+
+        assert!(verify_address_ownership_proofs(&proofs, &[testnet.clone()]).is_ok());
+
+        // The Ethereum prefix doesn't recover the signer of the testnet message
+        let wrong_prefix = ChainConfig {
+            message_prefix: ChainConfig::ethereum().message_prefix,
+            ..testnet
+        };
+        assert!(verify_address_ownership_proofs(&proofs, &[wrong_prefix]).is_err());
+
+        // Signatures on chains without a configuration are rejected
+        assert!(verify_address_ownership_proofs(&proofs, &[ChainConfig::ethereum()]).is_err());
+    }
+
+    #[test]
+    fn test_invalid_address_ownership_proofs() {
+        let chain_configs = vec![ChainConfig::ethereum()];
+
+        let proofs = parse_signature_csv("../csv/signatures.csv").unwrap();
+        assert!(invalid_address_ownership_proofs(&proofs, &chain_configs).is_empty());
+
+        // The second row claims the address of the first row with the signature of another address
+        let proofs = parse_signature_csv("../csv/signatures_one_invalid.csv").unwrap();
+        assert_eq!(
+            invalid_address_ownership_proofs(&proofs, &chain_configs),
+            vec![1]
+        );
+    }
+}
@@ -0,0 +1,62 @@
+use std::error::Error;
+
+/// The scheme used by a chain to derive an address from the key that signed a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressFormat {
+    /// 20 bytes hex address derived from the keccak256 hash of the public key, as used by Ethereum and EVM compatible chains
+    Evm,
+    /// Address derived from the blake2b hash of the public key, e.g. Polkadot
+    Blake2b,
+}
+
+/// Chain specific parameters used by the backend to verify address ownership proofs and to commit the cryptocurrencies of a round.
+///
+/// Supporting a new chain only requires providing its `ChainConfig`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainConfig {
+    /// The name of the chain as it appears in the `chain` column of the CSV files, e.g. "ETH"
+    pub name: String,
+    /// The chain id
+    pub id: u64,
+    /// The prefix prepended to a message before it is hashed and signed, e.g. the EIP-191 prefix for Ethereum
+    pub message_prefix: String,
+    pub address_format: AddressFormat,
+}
+
+impl ChainConfig {
+    pub fn new(name: &str, id: u64, message_prefix: &str, address_format: AddressFormat) -> Self {
+        ChainConfig {
+            name: name.to_string(),
+            id,
+            message_prefix: message_prefix.to_string(),
+            address_format,
+        }
+    }
+
+    /// The configuration of the Ethereum mainnet
+    pub fn ethereum() -> Self {
+        ChainConfig::new(
+            "ETH",
+            1,
+            "\x19Ethereum Signed Message:\n",
+            AddressFormat::Evm,
+        )
+    }
+}
+
+impl Default for ChainConfig {
+    fn default() -> Self {
+        ChainConfig::ethereum()
+    }
+}
+
+/// Returns the configuration of `chain` among `chain_configs`
+pub fn find_chain_config<'a>(
+    chain_configs: &'a [ChainConfig],
+    chain: &str,
+) -> Result<&'a ChainConfig, Box<dyn Error>> {
+    chain_configs
+        .iter()
+        .find(|chain_config| chain_config.name == chain)
+        .ok_or_else(|| format!("No configuration for chain {}", chain).into())
+}
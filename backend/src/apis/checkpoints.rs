@@ -0,0 +1,131 @@
+use ethers::types::U256;
+use std::{collections::BTreeMap, error::Error, fmt};
+
+use super::round::MstInclusionProof;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckpointError {
+    /// No root was published at the timestamp
+    UnknownTimestamp(u64),
+    /// A different root was already registered at the timestamp
+    AlreadyRegistered(u64),
+    /// The root of the proof is not the root published at the timestamp
+    RootMismatch(u64),
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckpointError::UnknownTimestamp(timestamp) => {
+                write!(f, "No root published at timestamp {}", timestamp)
+            }
+            CheckpointError::AlreadyRegistered(timestamp) => {
+                write!(
+                    f,
+                    "A different root is already published at timestamp {}",
+                    timestamp
+                )
+            }
+            CheckpointError::RootMismatch(timestamp) => {
+                write!(
+                    f,
+                    "The proof root doesn't match the root published at timestamp {}",
+                    timestamp
+                )
+            }
+        }
+    }
+}
+
+impl Error for CheckpointError {}
+
+/// The `(timestamp, root)` checkpoints of the roots published by the exchange, so that inclusion proofs against older roots can still be checked.
+#[derive(Debug, Clone, Default)]
+pub struct RootCheckpoints {
+    checkpoints: BTreeMap<u64, U256>,
+}
+
+impl RootCheckpoints {
+    pub fn new() -> Self {
+        RootCheckpoints {
+            checkpoints: BTreeMap::new(),
+        }
+    }
+
+    /// Registers the root published at `timestamp`. Registering the same root twice is a no-op, while a different root is rejected.
+    pub fn register(&mut self, timestamp: u64, root: U256) -> Result<(), CheckpointError> {
+        match self.checkpoints.get(&timestamp) {
+            Some(registered_root) if *registered_root != root => {
+                Err(CheckpointError::AlreadyRegistered(timestamp))
+            }
+            _ => {
+                self.checkpoints.insert(timestamp, root);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn get_root(&self, timestamp: u64) -> Option<&U256> {
+        self.checkpoints.get(&timestamp)
+    }
+
+    /// Checks that the root hash of `proof`, its second public input, is the root published at `timestamp`.
+    ///
+    /// The validity of the proof itself is checked by the inclusion verifier.
+    pub fn verify_inclusion_proof_at(
+        &self,
+        proof: &MstInclusionProof,
+        timestamp: u64,
+    ) -> Result<(), CheckpointError> {
+        let root = self
+            .checkpoints
+            .get(&timestamp)
+            .ok_or(CheckpointError::UnknownTimestamp(timestamp))?;
+
+        if proof.get_public_inputs().get(1) != Some(root) {
+            return Err(CheckpointError::RootMismatch(timestamp));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::round::Snapshot;
+    use summa_solvency::merkle_sum_tree::{MerkleSumTree, Tree};
+
+    #[test]
+    fn test_verify_inclusion_proof_at() {
+        let params_path = "ptau/hermez-raw-11";
+
+        let old_mst = MerkleSumTree::<2, 8>::from_csv("../csv/entry_16.csv").unwrap();
+        let new_mst = MerkleSumTree::<2, 8>::from_csv("../csv/entry_16_modified.csv").unwrap();
+        let to_u256 = |mst: &MerkleSumTree<2, 8>| {
+            U256::from_str_radix(&format!("{:?}", mst.root().hash), 16).unwrap()
+        };
+
+        let mut checkpoints = RootCheckpoints::new();
+        checkpoints.register(1, to_u256(&old_mst)).unwrap();
+        checkpoints.register(2, to_u256(&new_mst)).unwrap();
+        assert_eq!(
+            checkpoints.register(1, to_u256(&new_mst)),
+            Err(CheckpointError::AlreadyRegistered(1))
+        );
+
+        // A proof against the old root is still verified against the checkpoint of its timestamp
+        let snapshot = Snapshot::<4, 2, 8>::new(Box::new(old_mst), params_path).unwrap();
+        let proof = snapshot.generate_proof_of_inclusion(0).unwrap();
+
+        assert_eq!(checkpoints.verify_inclusion_proof_at(&proof, 1), Ok(()));
+        assert_eq!(
+            checkpoints.verify_inclusion_proof_at(&proof, 2),
+            Err(CheckpointError::RootMismatch(2))
+        );
+        assert_eq!(
+            checkpoints.verify_inclusion_proof_at(&proof, 3),
+            Err(CheckpointError::UnknownTimestamp(3))
+        );
+    }
+}
@@ -1,4 +1,7 @@
+pub mod address_deriver;
 pub mod address_ownership;
+pub mod chain_config;
+pub mod checkpoints;
 pub mod csv_parser;
 pub mod round;
 
@@ -1,19 +1,38 @@
 use ethers::types::{Bytes, U256};
 use halo2_proofs::{
-    halo2curves::bn256::{Bn256, G1Affine},
+    halo2curves::{
+        bn256::{Bn256, Fr as Fp, G1Affine},
+        ff::PrimeField,
+    },
     plonk::{ProvingKey, VerifyingKey},
     poly::kzg::commitment::ParamsKZG,
 };
+use num_bigint::BigUint;
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::error::Error;
+use std::{
+    collections::HashMap,
+    error::Error,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
 
+use super::chain_config::{find_chain_config, ChainConfig};
 use crate::contracts::{generated::summa_contract::summa::Cryptocurrency, signer::SummaSigner};
 use summa_solvency::{
     circuits::{
         merkle_sum_tree::MstInclusionCircuit,
-        utils::{gen_proof_solidity_calldata, generate_setup_artifacts},
+        utils::{
+            field_element_to_solidity_calldata, full_verifier_keccak, gen_proof_solidity_calldata,
+            generate_setup_artifacts,
+        },
+    },
+    merkle_sum_tree::{
+        utils::{commitment_digest, fp_to_big_uint, solvency_margin},
+        Tree,
     },
-    merkle_sum_tree::Tree,
 };
 
 pub(crate) type SetupArtifacts = (
@@ -22,7 +41,7 @@ pub(crate) type SetupArtifacts = (
     VerifyingKey<G1Affine>,
 );
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct MstInclusionProof {
     public_inputs: Vec<U256>,
     proof_calldata: Bytes,
@@ -38,6 +57,78 @@ impl MstInclusionProof {
     }
 }
 
+/// Flattens a proof generated with the Keccak256 transcript and its public inputs into the arguments of `verifyProof(bytes proof, uint256[] instances)` of the generated `InclusionVerifier.sol`.
+pub fn merkle_proof_to_calldata(proof: &[u8], public_inputs: &[Fp]) -> (Bytes, Vec<U256>) {
+    (
+        Bytes::from(proof.to_vec()),
+        public_inputs
+            .iter()
+            .map(|public_input| field_element_to_solidity_calldata(*public_input))
+            .collect(),
+    )
+}
+
+/// Converts a public input of the Solidity calldata back to a field element, or returns `None` if it isn't lower than the field modulus
+fn u256_to_fp(value: &U256) -> Option<Fp> {
+    let mut repr = [0u8; 32];
+    value.to_little_endian(&mut repr);
+    Option::<Fp>::from(Fp::from_repr(repr))
+}
+
+/// The metadata of a cryptocurrency of the round, as shown to the users
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CryptocurrencyMetadata {
+    pub name: String,
+    pub chain: String,
+    pub decimals: u8,
+}
+
+/// Everything a user needs to verify their inclusion in a round, in a single JSON-serializable download.
+///
+/// The public inputs of the inclusion proof are the leaf hash of the user, the root hash and the root balances, which the user checks against the commitment submitted on chain at `timestamp`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UserBundle {
+    pub timestamp: u64,
+    pub cryptocurrencies: Vec<CryptocurrencyMetadata>,
+    pub inclusion_proof: MstInclusionProof,
+}
+
+impl UserBundle {
+    /// Verifies the inclusion proof of the bundle offline, against the public setup and the verifying key of the inclusion circuit.
+    ///
+    /// Returns `false` if a public input isn't a valid field element.
+    pub fn verify(&self, params: &ParamsKZG<Bn256>, vk: &VerifyingKey<G1Affine>) -> bool {
+        let mut public_inputs = Vec::with_capacity(self.inclusion_proof.public_inputs.len());
+        for public_input in self.inclusion_proof.public_inputs.iter() {
+            match u256_to_fp(public_input) {
+                Some(public_input) => public_inputs.push(public_input),
+                None => return false,
+            }
+        }
+
+        full_verifier_keccak(
+            params,
+            vk,
+            self.inclusion_proof.proof_calldata.to_vec(),
+            vec![public_inputs],
+        )
+    }
+}
+
+/// Human-readable summary of a round for the operators of the exchange, serializable to JSON.
+///
+/// Amounts are decimal strings in the base unit of each cryptocurrency, in the order of `cryptocurrencies`. The margin of a cryptocurrency is its declared assets minus its total liabilities, negative if insolvent.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotSummary {
+    pub timestamp: u64,
+    pub total_users: usize,
+    pub cryptocurrencies: Vec<CryptocurrencyMetadata>,
+    pub total_liabilities: Vec<String>,
+    pub declared_assets: Vec<String>,
+    pub solvency_margins: Vec<String>,
+    pub commitment_digest: String,
+}
+
 pub struct Snapshot<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize> {
     pub mst: Box<dyn Tree<N_CURRENCIES>>,
     trusted_setup: SetupArtifacts,
@@ -47,6 +138,12 @@ pub struct Round<'a, const LEVELS: usize, const N_CURRENCIES: usize, const N_BYT
     timestamp: u64,
     snapshot: Snapshot<LEVELS, N_CURRENCIES, N_BYTES>,
     signer: &'a SummaSigner,
+    chain_configs: Vec<ChainConfig>,
+    // The public inputs committed on chain by `dispatch_commitment`, namely the root hash followed by the root balances. The inclusion proofs of the round verify against them
+    pub(crate) commitment_instances: Vec<Fp>,
+    // Inclusion proofs already generated in this round, indexed by user index
+    proof_cache: Mutex<HashMap<usize, MstInclusionProof>>,
+    proof_generation_count: AtomicUsize,
 }
 
 impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize>
@@ -64,10 +161,47 @@ where
     where
         [(); N_CURRENCIES + 2]: Sized,
     {
+        Self::new_with_chain_configs(
+            signer,
+            mst,
+            params_path,
+            timestamp,
+            vec![ChainConfig::default()],
+        )
+    }
+
+    /// Same as `new`, with the configurations of the chains the cryptocurrencies of the round may belong to
+    pub fn new_with_chain_configs<'a>(
+        signer: &'a SummaSigner,
+        mst: Box<dyn Tree<N_CURRENCIES>>,
+        params_path: &str,
+        timestamp: u64,
+        chain_configs: Vec<ChainConfig>,
+    ) -> Result<Round<'a, LEVELS, N_CURRENCIES, N_BYTES>, Box<dyn Error>>
+    where
+        [(); N_CURRENCIES + 2]: Sized,
+    {
+        // The cryptocurrencies are only known at runtime, so a tree built with a mismatching list is caught here rather than when committing
+        if mst.cryptocurrencies().len() != N_CURRENCIES {
+            return Err(format!(
+                "The tree has {} cryptocurrencies, expected N_CURRENCIES = {}",
+                mst.cryptocurrencies().len(),
+                N_CURRENCIES
+            )
+            .into());
+        }
+
+        let mut commitment_instances = vec![mst.root().hash];
+        commitment_instances.extend_from_slice(&mst.root().balances);
+
         Ok(Round {
             timestamp,
             snapshot: Snapshot::<LEVELS, N_CURRENCIES, N_BYTES>::new(mst, params_path).unwrap(),
             signer: &signer,
+            chain_configs,
+            commitment_instances,
+            proof_cache: Mutex::new(HashMap::new()),
+            proof_generation_count: AtomicUsize::new(0),
         })
     }
 
@@ -75,13 +209,63 @@ where
         self.timestamp
     }
 
-    pub async fn dispatch_commitment(&mut self) -> Result<(), Box<dyn Error>> {
-        let root_str = format!("{:?}", self.snapshot.mst.root().hash);
+    /// Returns the single value to display publicly as the anchor of the round, binding the root hash, the root balances and the timestamp
+    pub fn commitment_digest(&self) -> Fp {
+        commitment_digest(self.snapshot.mst.root(), self.timestamp)
+    }
+
+    pub fn get_chain_configs(&self) -> &Vec<ChainConfig> {
+        &self.chain_configs
+    }
+
+    /// Checks locally that the inclusion proofs of the round verify against the instances that `dispatch_commitment` is about to commit, so that a mismatching commitment doesn't waste a transaction.
+    ///
+    /// The inclusion proof of the first user is verified with the committed root hash and root balances. The proof is taken from the cache of the round, so it is generated at most once per round and later served to the user rather than proven again at every dispatch.
+    pub fn validate_before_dispatch(&self) -> Result<(), &'static str>
+    where
+        [(); N_CURRENCIES + 2]: Sized,
+    {
+        let inclusion_proof = self.get_proof_of_inclusion(0)?;
+
+        // The leaf hash followed by the committed instances
+        let leaf_hash = inclusion_proof
+            .public_inputs
+            .first()
+            .and_then(u256_to_fp)
+            .ok_or("Invalid leaf hash in the inclusion proof")?;
+        let mut instances = vec![leaf_hash];
+        instances.extend_from_slice(&self.commitment_instances);
+
+        let (params, _, vk) = &self.snapshot.trusted_setup;
+        if full_verifier_keccak(
+            params,
+            vk,
+            inclusion_proof.proof_calldata.to_vec(),
+            vec![instances],
+        ) {
+            Ok(())
+        } else {
+            Err("The inclusion proofs don't verify against the committed instances")
+        }
+    }
+
+    pub async fn dispatch_commitment(&mut self) -> Result<(), Box<dyn Error>>
+    where
+        [(); N_CURRENCIES + 2]: Sized,
+    {
+        // Every cryptocurrency must belong to a configured chain
+        for cryptocurrency in self.snapshot.mst.cryptocurrencies() {
+            find_chain_config(&self.chain_configs, &cryptocurrency.chain)?;
+        }
+
+        self.validate_before_dispatch()?;
+
+        let root_str = format!("{:?}", self.commitment_instances[0]);
         let mst_root = U256::from_str_radix(&root_str, 16).unwrap();
 
         let mut root_sums = Vec::<U256>::new();
 
-        for balance in self.snapshot.mst.root().balances.iter() {
+        for balance in self.commitment_instances[1..].iter() {
             let fp_str = format!("{:?}", balance);
             root_sums.push(U256::from_str_radix(&fp_str, 16).unwrap());
         }
@@ -97,6 +281,7 @@ where
                     .map(|cryptocurrency| Cryptocurrency {
                         name: cryptocurrency.name.clone(),
                         chain: cryptocurrency.chain.clone(),
+                        decimals: cryptocurrency.decimals,
                     })
                     .collect::<Vec<Cryptocurrency>>()
                     .as_slice()
@@ -109,6 +294,7 @@ where
         Ok(())
     }
 
+    /// Returns the inclusion proof of the user at `user_index`. The proof is generated on the first request and served from the cache of the round afterwards.
     pub fn get_proof_of_inclusion(
         &self,
         user_index: usize,
@@ -116,10 +302,126 @@ where
     where
         [(); N_CURRENCIES + 2]: Sized,
     {
-        Ok(self
+        if let Some(proof) = self.proof_cache.lock().unwrap().get(&user_index) {
+            return Ok(proof.clone());
+        }
+
+        // The lock is not held while proving, so that proofs for different users can be generated concurrently
+        let proof = self
             .snapshot
             .generate_proof_of_inclusion(user_index)
-            .unwrap())
+            .unwrap();
+        self.proof_generation_count.fetch_add(1, Ordering::SeqCst);
+
+        Ok(self
+            .proof_cache
+            .lock()
+            .unwrap()
+            .entry(user_index)
+            .or_insert(proof)
+            .clone())
+    }
+
+    /// Returns the bundle of the user at `user_index`, combining their inclusion proof, the metadata of the cryptocurrencies and the timestamp of the round.
+    pub fn get_user_bundle(&self, user_index: usize) -> Result<UserBundle, &'static str>
+    where
+        [(); N_CURRENCIES + 2]: Sized,
+    {
+        let inclusion_proof = self.get_proof_of_inclusion(user_index)?;
+
+        Ok(UserBundle {
+            timestamp: self.timestamp,
+            cryptocurrencies: self.cryptocurrency_metadata(),
+            inclusion_proof,
+        })
+    }
+
+    /// Returns the summary of the round given the assets declared by the exchange for each cryptocurrency, in the order of the cryptocurrencies of the tree.
+    ///
+    /// The padding leaves of the tree are not counted as users. Returns an error if a solvency margin doesn't fit in an `i128`, see `solvency_margin`.
+    pub fn summary(
+        &self,
+        asset_sums: &[BigUint; N_CURRENCIES],
+    ) -> Result<SnapshotSummary, &'static str> {
+        let solvency_margins = solvency_margin(self.snapshot.mst.as_ref(), asset_sums)
+            .ok_or("solvency margin doesn't fit in an i128")?;
+
+        Ok(SnapshotSummary {
+            timestamp: self.timestamp,
+            total_users: self.snapshot.mst.real_entry_count(),
+            cryptocurrencies: self.cryptocurrency_metadata(),
+            total_liabilities: self
+                .snapshot
+                .mst
+                .root()
+                .balances
+                .iter()
+                .map(|balance| fp_to_big_uint(*balance).to_string())
+                .collect(),
+            declared_assets: asset_sums.iter().map(|sum| sum.to_string()).collect(),
+            solvency_margins: solvency_margins
+                .iter()
+                .map(|margin| margin.to_string())
+                .collect(),
+            commitment_digest: format!("{:?}", self.commitment_digest()),
+        })
+    }
+
+    fn cryptocurrency_metadata(&self) -> Vec<CryptocurrencyMetadata> {
+        self.snapshot
+            .mst
+            .cryptocurrencies()
+            .iter()
+            .map(|cryptocurrency| CryptocurrencyMetadata {
+                name: cryptocurrency.name.clone(),
+                chain: cryptocurrency.chain.clone(),
+                decimals: cryptocurrency.decimals,
+            })
+            .collect()
+    }
+
+    /// Returns the inclusion proofs of the users at `indices`, in the same order.
+    ///
+    /// The proofs missing from the cache of the round are generated in parallel and cached.
+    pub fn get_proofs_of_inclusion(
+        &self,
+        indices: &[usize],
+    ) -> Result<Vec<MstInclusionProof>, &'static str>
+    where
+        [(); N_CURRENCIES + 2]: Sized,
+    {
+        let missing_indices: Vec<usize> = {
+            let proof_cache = self.proof_cache.lock().unwrap();
+            let mut missing_indices: Vec<usize> = indices
+                .iter()
+                .filter(|index| !proof_cache.contains_key(index))
+                .copied()
+                .collect();
+            missing_indices.sort_unstable();
+            missing_indices.dedup();
+            missing_indices
+        };
+
+        let generated_proofs = self
+            .snapshot
+            .generate_proofs_of_inclusion(&missing_indices)?;
+        self.proof_generation_count
+            .fetch_add(generated_proofs.len(), Ordering::SeqCst);
+
+        let mut proof_cache = self.proof_cache.lock().unwrap();
+        for (index, proof) in missing_indices.into_iter().zip(generated_proofs) {
+            proof_cache.entry(index).or_insert(proof);
+        }
+
+        Ok(indices
+            .iter()
+            .map(|index| proof_cache[index].clone())
+            .collect())
+    }
+
+    /// Returns the number of inclusion proofs generated, as opposed to served from the cache, in this round
+    pub(crate) fn get_proof_generation_count(&self) -> usize {
+        self.proof_generation_count.load(Ordering::SeqCst)
     }
 }
 
@@ -172,4 +474,37 @@ where
             public_inputs: calldata.1,
         })
     }
+
+    /// Generates the inclusion proofs of the users at `indices` in parallel, in the same order.
+    pub fn generate_proofs_of_inclusion(
+        &self,
+        indices: &[usize],
+    ) -> Result<Vec<MstInclusionProof>, &'static str>
+    where
+        [(); N_CURRENCIES + 2]: Sized,
+    {
+        // The merkle proofs are read from the tree sequentially, only the proving runs in parallel
+        let circuits = indices
+            .iter()
+            .map(|index| {
+                self.mst
+                    .generate_proof(*index)
+                    .map(MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init)
+                    .map_err(|_| "Failed to generate the merkle proof")
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (params, pk, _) = &self.trusted_setup;
+
+        Ok(circuits
+            .into_par_iter()
+            .map(|circuit| {
+                let calldata = gen_proof_solidity_calldata(params, pk, circuit);
+                MstInclusionProof {
+                    proof_calldata: calldata.0,
+                    public_inputs: calldata.1,
+                }
+            })
+            .collect())
+    }
 }
@@ -5,8 +5,11 @@ use ethers::{
     types::{Address, U256},
 };
 use serde_json::Value;
-use std::{error::Error, fs::File, io::BufReader, path::Path, str::FromStr, sync::Arc};
-use tokio::sync::Mutex;
+use std::{
+    error::Error, fmt, fs::File, future::Future, io::BufReader, path::Path, str::FromStr,
+    sync::Arc, time::Duration,
+};
+use tokio::{sync::Mutex, time::timeout};
 
 use super::generated::summa_contract::{AddressOwnershipProof, Cryptocurrency};
 use crate::contracts::generated::summa_contract::Summa;
@@ -16,14 +19,47 @@ pub enum AddressInput {
     Path(String),
 }
 
+/// The default time after which an RPC call of the signer is abandoned
+pub const DEFAULT_RPC_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug)]
+pub enum SignerError {
+    /// The RPC endpoint didn't respond within the configured timeout
+    Timeout(Duration),
+}
+
+impl fmt::Display for SignerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignerError::Timeout(duration) => {
+                write!(f, "RPC call timed out after {:?}", duration)
+            }
+        }
+    }
+}
+
+impl Error for SignerError {}
+
+/// Awaits `future`, returning `SignerError::Timeout` if it doesn't complete within `duration`
+async fn with_timeout<T, E: Error + 'static>(
+    duration: Duration,
+    future: impl Future<Output = Result<T, E>>,
+) -> Result<T, Box<dyn Error>> {
+    match timeout(duration, future).await {
+        Ok(result) => Ok(result?),
+        Err(_) => Err(Box::new(SignerError::Timeout(duration))),
+    }
+}
+
 #[derive(Debug)]
 pub struct SummaSigner {
     nonce_lock: Mutex<()>, // To prevent running `submit` methods concurrently
     summa_contract: Summa<SignerMiddleware<Arc<Provider<Http>>, LocalWallet>>,
+    rpc_timeout: Duration,
 }
 
 impl SummaSigner {
-    /// Creates a new SummaSigner instance
+    /// Creates a new SummaSigner instance, whose RPC calls time out after `DEFAULT_RPC_TIMEOUT`
     /// # Arguments
     /// * `signer_key` - The private key of wallet that will interact with the chain on behalf of the exchange
     /// * `url` -  The endpoint for connecting to the node
@@ -32,11 +68,28 @@ impl SummaSigner {
         signer_key: &str,
         url: &str,
         address_input: AddressInput,
+    ) -> Result<Self, Box<dyn Error>> {
+        Self::new_with_timeout(signer_key, url, address_input, DEFAULT_RPC_TIMEOUT).await
+    }
+
+    /// Creates a new SummaSigner instance
+    /// # Arguments
+    /// * `signer_key` - The private key of wallet that will interact with the chain on behalf of the exchange
+    /// * `url` -  The endpoint for connecting to the node
+    /// * `address` - The address of the Summa contract
+    /// * `rpc_timeout` - The time after which an RPC call returns `SignerError::Timeout` instead of blocking. It doesn't apply to the wait for a sent transaction to be mined, as a transaction may take longer to be mined on a congested chain, and reporting it as timed out would lead the caller to submit it again
+    pub async fn new_with_timeout(
+        signer_key: &str,
+        url: &str,
+        address_input: AddressInput,
+        rpc_timeout: Duration,
     ) -> Result<Self, Box<dyn Error>> {
         let wallet: LocalWallet = LocalWallet::from_str(signer_key).unwrap();
 
         let provider = Arc::new(Provider::try_from(url)?);
-        let chain_id = provider.get_chainid().await?.as_u64();
+        let chain_id = with_timeout(rpc_timeout, provider.get_chainid())
+            .await?
+            .as_u64();
         let client = Arc::new(SignerMiddleware::new(
             provider,
             wallet.with_chain_id(chain_id),
@@ -53,6 +106,7 @@ impl SummaSigner {
         Ok(Self {
             nonce_lock: Mutex::new(()),
             summa_contract: Summa::new(address, client),
+            rpc_timeout,
         })
     }
 
@@ -95,7 +149,7 @@ impl SummaSigner {
             .submit_proof_of_address_ownership(address_ownership_proofs);
 
         // To prevent nonce collision, we lock the nonce before sending the transaction
-        let tx = submit_proof_of_address_ownership.send().await?;
+        let tx = with_timeout(self.rpc_timeout, submit_proof_of_address_ownership.send()).await?;
 
         // Wait for the pending transaction to be mined
         tx.await?;
@@ -121,7 +175,7 @@ impl SummaSigner {
         );
 
         // To prevent nonce collision, we lock the nonce before sending the transaction
-        let tx = submit_liability_commitment.send().await?;
+        let tx = with_timeout(self.rpc_timeout, submit_liability_commitment.send()).await?;
 
         // Wait for the pending transaction to be mined
         tx.await?;
@@ -99,28 +99,68 @@ pub async fn initialize_test_env(
 #[cfg(test)]
 mod test {
     use ethers::{
-        abi::AbiEncode,
+        abi::{AbiDecode, AbiEncode},
         providers::{Http, Middleware, Provider},
-        types::{U256, U64},
+        types::{Address, U256, U64},
         utils::to_checksum,
     };
+    use halo2_proofs::halo2curves::{bn256::Fr as Fp, ff::PrimeField};
     use std::{convert::TryFrom, error::Error};
-    use summa_solvency::merkle_sum_tree::MerkleSumTree;
+    use summa_solvency::merkle_sum_tree::{utils::fp_to_big_uint, MerkleSumTree, Tree};
     use tokio::{
         join,
-        time::{sleep, Duration},
+        time::{sleep, Duration, Instant},
     };
 
-    use crate::apis::{address_ownership::AddressOwnership, round::Round};
+    use summa_solvency::circuits::{
+        merkle_sum_tree::MstInclusionCircuit, utils::generate_setup_artifacts, WithInstances,
+    };
+
+    use crate::apis::{
+        address_ownership::AddressOwnership,
+        round::{merkle_proof_to_calldata, Round, SnapshotSummary, UserBundle},
+    };
     use crate::contracts::{
+        generated::inclusion_verifier::VerifyProofCall,
         generated::summa_contract::{
             AddressOwnershipProof, AddressOwnershipProofSubmittedFilter, Cryptocurrency,
             LiabilitiesCommitmentSubmittedFilter,
         },
-        signer::{AddressInput, SummaSigner},
+        signer::{AddressInput, SignerError, SummaSigner},
     };
     use crate::tests::initialize_test_env;
 
+    #[test]
+    fn test_merkle_proof_to_calldata() {
+        let mst = MerkleSumTree::<2, 8>::from_csv("../csv/entry_16.csv").unwrap();
+        let circuit = MstInclusionCircuit::<4, 2, 8>::init(mst.generate_proof(0).unwrap());
+        let public_inputs = circuit.instances()[0].clone();
+
+        // Only the layout of the calldata is checked, so the proof bytes are arbitrary
+        let proof: Vec<u8> = (0..=255).collect();
+
+        let (proof_calldata, instances) = merkle_proof_to_calldata(&proof, &public_inputs);
+        let encoded_call = VerifyProofCall {
+            proof: proof_calldata,
+            instances,
+        }
+        .encode();
+
+        let decoded_call = VerifyProofCall::decode(&encoded_call).unwrap();
+        assert_eq!(decoded_call.proof.to_vec(), proof);
+
+        let decoded_public_inputs: Vec<Fp> = decoded_call
+            .instances
+            .iter()
+            .map(|instance| {
+                let mut repr = [0u8; 32];
+                instance.to_little_endian(&mut repr);
+                Fp::from_repr(repr).unwrap()
+            })
+            .collect();
+        assert_eq!(decoded_public_inputs, public_inputs);
+    }
+
     #[tokio::test]
     async fn test_deployed_address() -> Result<(), Box<dyn Error>> {
         let (anvil, _, _, _, summa_contract) = initialize_test_env(None).await;
@@ -142,6 +182,40 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_signer_rpc_timeout() -> Result<(), Box<dyn Error>> {
+        // An endpoint that accepts connections but never responds
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let endpoint = format!("http://{}", listener.local_addr()?);
+        tokio::spawn(async move {
+            let mut connections = vec![];
+            while let Ok((stream, _)) = listener.accept().await {
+                connections.push(stream);
+            }
+        });
+
+        let rpc_timeout = Duration::from_secs(1);
+        let start = Instant::now();
+
+        let result = SummaSigner::new_with_timeout(
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+            endpoint.as_str(),
+            AddressInput::Address(Address::zero()),
+            rpc_timeout,
+        )
+        .await;
+
+        // The call should return a timeout error within the configured window rather than hang
+        let error = result.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<SignerError>(),
+            Some(SignerError::Timeout(duration)) if *duration == rpc_timeout
+        ));
+        assert!(start.elapsed() < rpc_timeout * 3);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_concurrent_sumbit_commitments() -> Result<(), Box<dyn Error>> {
         let (anvil, _, _, _, summa_contract) = initialize_test_env(Some(1)).await;
@@ -190,6 +264,71 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_dispatch_commitment_decimals() -> Result<(), Box<dyn Error>> {
+        let (anvil, _, _, _, summa_contract) = initialize_test_env(None).await;
+
+        let signer = SummaSigner::new(
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+            anvil.endpoint().as_str(),
+            AddressInput::Address(summa_contract.address()),
+        )
+        .await?;
+
+        let mst = MerkleSumTree::<2, 8>::from_csv("../csv/entry_16_decimals.csv").unwrap();
+        let mut round =
+            Round::<4, 2, 8>::new(&signer, Box::new(mst), "ptau/hermez-raw-11", 1).unwrap();
+
+        round.dispatch_commitment().await?;
+
+        // The decimals parsed from the CSV header are part of the submitted metadata
+        let liability_commitment_logs = summa_contract
+            .liabilities_commitment_submitted_filter()
+            .query()
+            .await?;
+
+        assert_eq!(liability_commitment_logs.len(), 1);
+        assert_eq!(
+            liability_commitment_logs[0].cryptocurrencies,
+            vec![
+                Cryptocurrency {
+                    name: "ETH".to_string(),
+                    chain: "ETH".to_string(),
+                    decimals: 18,
+                },
+                Cryptocurrency {
+                    name: "USDT".to_string(),
+                    chain: "ETH".to_string(),
+                    decimals: 6,
+                },
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_verify_all_offline() -> Result<(), Box<dyn Error>> {
+        let (anvil, _, _, _, summa_contract) = initialize_test_env(None).await;
+
+        let signer = SummaSigner::new(
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+            anvil.endpoint().as_str(),
+            AddressInput::Address(summa_contract.address()),
+        )
+        .await?;
+
+        let address_ownership_client = AddressOwnership::new(&signer, "../csv/signatures.csv")?;
+        assert_eq!(address_ownership_client.verify_all_offline(), Ok(()));
+
+        // The second row claims the address of the first row with the signature of another address
+        let address_ownership_client =
+            AddressOwnership::new(&signer, "../csv/signatures_one_invalid.csv")?;
+        assert_eq!(address_ownership_client.verify_all_offline(), Err(vec![1]));
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_round_features() -> Result<(), Box<dyn Error>> {
         let (anvil, cex_addr_1, cex_addr_2, _, summa_contract) = initialize_test_env(None).await;
@@ -270,10 +409,12 @@ mod test {
                     Cryptocurrency {
                         name: "ETH".to_string(),
                         chain: "ETH".to_string(),
+                        decimals: 0,
                     },
                     Cryptocurrency {
                         name: "USDT".to_string(),
                         chain: "ETH".to_string(),
+                        decimals: 0,
                     },
                 ],
             }
@@ -296,4 +437,253 @@ mod test {
         drop(anvil);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_round_mismatching_cryptocurrencies() -> Result<(), Box<dyn Error>> {
+        let (anvil, _, _, _, summa_contract) = initialize_test_env(None).await;
+
+        let signer = SummaSigner::new(
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+            anvil.endpoint().as_str(),
+            AddressInput::Address(summa_contract.address()),
+        )
+        .await?;
+
+        let mst = MerkleSumTree::<2, 8>::from_csv("../csv/entry_16.csv").unwrap();
+
+        // A tree whose cryptocurrencies don't match N_CURRENCIES is rejected
+        let mut mismatching_cryptocurrencies = mst.cryptocurrencies().to_vec();
+        let mut extra_cryptocurrency = mismatching_cryptocurrencies[0].clone();
+        extra_cryptocurrency.name = "BTC".to_string();
+        mismatching_cryptocurrencies.push(extra_cryptocurrency);
+        let mismatching_mst = MerkleSumTree::<2, 8>::from_entries(
+            mst.entries().to_vec(),
+            mismatching_cryptocurrencies,
+            false,
+        )
+        .unwrap();
+        let mismatching_round =
+            Round::<4, 2, 8>::new(&signer, Box::new(mismatching_mst), "ptau/hermez-raw-11", 1);
+        assert_eq!(
+            mismatching_round.err().unwrap().to_string(),
+            "The tree has 3 cryptocurrencies, expected N_CURRENCIES = 2"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_validate_before_dispatch() -> Result<(), Box<dyn Error>> {
+        let (anvil, _, _, _, summa_contract) = initialize_test_env(None).await;
+
+        let signer = SummaSigner::new(
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+            anvil.endpoint().as_str(),
+            AddressInput::Address(summa_contract.address()),
+        )
+        .await?;
+
+        let mst = MerkleSumTree::<2, 8>::from_csv("../csv/entry_16.csv").unwrap();
+        let mut round =
+            Round::<4, 2, 8>::new(&signer, Box::new(mst), "ptau/hermez-raw-11", 1).unwrap();
+
+        // A commitment that doesn't match the inclusion proofs aborts the dispatch before sending any transaction
+        let committed_root_hash = round.commitment_instances[0];
+        round.commitment_instances[0] = Fp::from(1u64);
+        assert!(round.validate_before_dispatch().is_err());
+        assert!(round.dispatch_commitment().await.is_err());
+
+        let liability_commitment_logs = summa_contract
+            .liabilities_commitment_submitted_filter()
+            .query()
+            .await?;
+        assert_eq!(liability_commitment_logs.len(), 0);
+
+        round.commitment_instances[0] = committed_root_hash;
+        round.dispatch_commitment().await?;
+
+        let liability_commitment_logs = summa_contract
+            .liabilities_commitment_submitted_filter()
+            .query()
+            .await?;
+        assert_eq!(liability_commitment_logs.len(), 1);
+
+        // Every validation used the cached inclusion proof of the first user, which was only generated once
+        assert_eq!(round.get_proof_generation_count(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_inclusion_proof_cache() -> Result<(), Box<dyn Error>> {
+        let (anvil, _, _, _, summa_contract) = initialize_test_env(None).await;
+
+        let signer = SummaSigner::new(
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+            anvil.endpoint().as_str(),
+            AddressInput::Address(summa_contract.address()),
+        )
+        .await?;
+
+        let mst = MerkleSumTree::<2, 8>::from_csv("../csv/entry_16.csv").unwrap();
+        let round = Round::<4, 2, 8>::new(&signer, Box::new(mst), "ptau/hermez-raw-11", 1).unwrap();
+
+        let inclusion_proof = round.get_proof_of_inclusion(0).unwrap();
+        assert_eq!(round.get_proof_generation_count(), 1);
+
+        // A second request for the same user is served from the cache of the round
+        let cached_inclusion_proof = round.get_proof_of_inclusion(0).unwrap();
+        assert_eq!(cached_inclusion_proof, inclusion_proof);
+        assert_eq!(round.get_proof_generation_count(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_parallel_inclusion_proofs() -> Result<(), Box<dyn Error>> {
+        let (anvil, _, _, _, summa_contract) = initialize_test_env(None).await;
+
+        let signer = SummaSigner::new(
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+            anvil.endpoint().as_str(),
+            AddressInput::Address(summa_contract.address()),
+        )
+        .await?;
+
+        let params_path = "ptau/hermez-raw-11";
+        let entry_csv = "../csv/entry_16.csv";
+
+        let mst = MerkleSumTree::<2, 8>::from_csv(entry_csv).unwrap();
+        let mut round = Round::<4, 2, 8>::new(&signer, Box::new(mst), params_path, 1).unwrap();
+
+        // The commitment is dispatched so that the proofs can be verified on chain. Its validation generates the proof of the first user
+        round.dispatch_commitment().await?;
+        let inclusion_proof = round.get_proof_of_inclusion(0).unwrap();
+        assert_eq!(round.get_proof_generation_count(), 1);
+
+        // Proofs for several users are generated in parallel, only for the users missing from the cache
+        let indices = [0, 1, 2, 3];
+        let batch_proofs = round.get_proofs_of_inclusion(&indices).unwrap();
+        assert_eq!(batch_proofs.len(), indices.len());
+        assert_eq!(batch_proofs[0], inclusion_proof);
+        assert_eq!(round.get_proof_generation_count(), 4);
+
+        // The proofs are blinded with fresh randomness, so they are compared with sequential proofs of a separate round through their public inputs
+        let sequential_round = Round::<4, 2, 8>::new(
+            &signer,
+            Box::new(MerkleSumTree::<2, 8>::from_csv(entry_csv).unwrap()),
+            params_path,
+            1,
+        )
+        .unwrap();
+        for (index, batch_proof) in indices.iter().zip(batch_proofs.iter()) {
+            let sequential_proof = sequential_round.get_proof_of_inclusion(*index).unwrap();
+            assert_eq!(
+                batch_proof.get_public_inputs(),
+                sequential_proof.get_public_inputs()
+            );
+
+            // The cached proof is served for later requests
+            assert_eq!(round.get_proof_of_inclusion(*index).unwrap(), *batch_proof);
+
+            let verified = summa_contract
+                .verify_inclusion_proof(
+                    batch_proof.get_proof().clone(),
+                    batch_proof.get_public_inputs().clone(),
+                    U256::from(1),
+                )
+                .await?;
+            assert!(verified);
+        }
+        assert_eq!(round.get_proof_generation_count(), 4);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_user_bundle() -> Result<(), Box<dyn Error>> {
+        let (anvil, _, _, _, summa_contract) = initialize_test_env(None).await;
+
+        let signer = SummaSigner::new(
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+            anvil.endpoint().as_str(),
+            AddressInput::Address(summa_contract.address()),
+        )
+        .await?;
+
+        let params_path = "ptau/hermez-raw-11";
+        let mst = MerkleSumTree::<2, 8>::from_csv("../csv/entry_16.csv").unwrap();
+        let round = Round::<4, 2, 8>::new(&signer, Box::new(mst), params_path, 1).unwrap();
+
+        // The bundle of a user survives a JSON round trip and verifies offline with the verifying key of the inclusion circuit
+        let bundle = round.get_user_bundle(0).unwrap();
+        assert_eq!(bundle.timestamp, 1);
+        assert_eq!(
+            bundle.inclusion_proof,
+            round.get_proof_of_inclusion(0).unwrap()
+        );
+        assert_eq!(bundle.cryptocurrencies.len(), 2);
+        assert_eq!(bundle.cryptocurrencies[0].name, "ETH");
+        assert_eq!(bundle.cryptocurrencies[1].name, "USDT");
+
+        let bundle_json = serde_json::to_string(&bundle)?;
+        let downloaded_bundle: UserBundle = serde_json::from_str(&bundle_json)?;
+        assert_eq!(downloaded_bundle, bundle);
+
+        let (params, _, vk) = generate_setup_artifacts(
+            11,
+            Some(params_path),
+            MstInclusionCircuit::<4, 2, 8>::init_empty(),
+        )
+        .unwrap();
+        assert!(downloaded_bundle.verify(&params, &vk));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_round_summary() -> Result<(), Box<dyn Error>> {
+        let (anvil, _, _, _, summa_contract) = initialize_test_env(None).await;
+
+        let signer = SummaSigner::new(
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+            anvil.endpoint().as_str(),
+            AddressInput::Address(summa_contract.address()),
+        )
+        .await?;
+
+        // The summary of a round over a padded tree only counts the real users
+        let padded_mst = MerkleSumTree::<2, 8>::from_csv("../csv/entry_13.csv").unwrap();
+        let liabilities = padded_mst.root().balances.map(fp_to_big_uint);
+        let padded_round =
+            Round::<4, 2, 8>::new(&signer, Box::new(padded_mst), "ptau/hermez-raw-11", 1).unwrap();
+
+        let asset_sums = [
+            liabilities[0].clone() + 100u32,
+            liabilities[1].clone() - 1u32,
+        ];
+        let summary = padded_round.summary(&asset_sums).unwrap();
+        assert_eq!(summary.total_users, 13);
+        assert_eq!(
+            summary.total_liabilities,
+            vec![liabilities[0].to_string(), liabilities[1].to_string()]
+        );
+        assert_eq!(
+            summary.declared_assets,
+            vec![asset_sums[0].to_string(), asset_sums[1].to_string()]
+        );
+        assert_eq!(summary.solvency_margins, vec!["100", "-1"]);
+        assert_eq!(
+            summary.commitment_digest,
+            format!("{:?}", padded_round.commitment_digest())
+        );
+
+        let summary_json = serde_json::to_string(&summary)?;
+        assert_eq!(
+            serde_json::from_str::<SnapshotSummary>(&summary_json)?,
+            summary
+        );
+
+        Ok(())
+    }
 }
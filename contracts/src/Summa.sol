@@ -38,10 +38,12 @@ contract Summa is Ownable {
      * @dev Struct identifying a cryptocurrency traded on the CEX
      * @param name The name of the cryptocurrency
      * @param chain The name of the chain name where the cryptocurrency lives (e.g., ETH, BTC)
+     * @param decimals The number of decimal places of the base unit in which the balances are expressed (e.g., 18 for ETH)
      */
     struct Cryptocurrency {
         string name;
         string chain;
+        uint8 decimals;
     }
 
     /**
@@ -50,12 +52,14 @@ contract Summa is Ownable {
      * @param rootBalances The total sums of the liabilities included in the tree
      * @param blockchainNames The names of the blockchains where the CEX holds the cryptocurrencies included into the tree
      * @param cryptocurrencyNames The names of the cryptocurrencies included into the tree
+     * @param cryptocurrencyDecimals The decimals of the cryptocurrencies included into the tree
      */
     struct Commitment {
         uint256 mstRoot;
         uint256[] rootBalances;
         string[] cryptocurrencyNames;
         string[] blockchainNames;
+        uint8[] cryptocurrencyDecimals;
     }
 
     // Summa configuration
@@ -156,6 +160,9 @@ contract Summa is Ownable {
             cryptocurrencies.length
         );
         string[] memory blockchainNames = new string[](cryptocurrencies.length);
+        uint8[] memory cryptocurrencyDecimals = new uint8[](
+            cryptocurrencies.length
+        );
         for (uint i = 0; i < cryptocurrencies.length; i++) {
             require(
                 bytes(cryptocurrencies[i].chain).length != 0 &&
@@ -168,13 +175,15 @@ contract Summa is Ownable {
             );
             cryptocurrencyNames[i] = cryptocurrencies[i].name;
             blockchainNames[i] = cryptocurrencies[i].chain;
+            cryptocurrencyDecimals[i] = cryptocurrencies[i].decimals;
         }
 
         commitments[timestamp] = Commitment(
             mstRoot,
             rootBalances,
             cryptocurrencyNames,
-            blockchainNames
+            blockchainNames,
+            cryptocurrencyDecimals
         );
 
         emit LiabilitiesCommitmentSubmitted(
@@ -17,10 +17,12 @@ describe("Summa Contract", () => {
       {
         chain: "ETH",
         name: "ETH",
+        decimals: 18,
       },
       {
         chain: "BTC",
         name: "BTC",
+        decimals: 8,
       },
     ]
   ): any {
@@ -283,7 +285,8 @@ describe("Summa Contract", () => {
           (cryptocurrencies: [Summa.CryptocurrencyStruct]) => {
             return (
               cryptocurrencies[0].chain == "ETH" &&
-              cryptocurrencies[0].name == "ETH"
+              cryptocurrencies[0].name == "ETH" &&
+              cryptocurrencies[0].decimals == 18
             );
           }
         );
@@ -298,6 +301,7 @@ describe("Summa Contract", () => {
             {
               chain: "ETH",
               name: "ETH",
+              decimals: 18,
             },
           ],
           BigNumber.from(1693559255)
@@ -323,10 +327,12 @@ describe("Summa Contract", () => {
           {
             chain: "BTC",
             name: "BTC",
+            decimals: 8,
           },
           {
             chain: "",
             name: "ETH",
+            decimals: 18,
           },
         ])
       ).to.be.revertedWith("Invalid cryptocurrency");
@@ -336,10 +342,12 @@ describe("Summa Contract", () => {
           {
             chain: "ETH",
             name: "ETH",
+            decimals: 18,
           },
           {
             chain: "BTC",
             name: "",
+            decimals: 8,
           },
         ])
       ).to.be.revertedWith("Invalid cryptocurrency");
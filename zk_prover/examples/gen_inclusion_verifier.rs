@@ -1,13 +1,14 @@
 #![feature(generic_const_exprs)]
 
 use halo2_proofs::halo2curves::{bn256::Fr as Fp, ff::PrimeField};
-use num_bigint::BigInt;
+use num_bigint::BigUint;
 use num_traits::Num;
 use prelude::*;
 
 use halo2_solidity_verifier::{compile_solidity, BatchOpenScheme::Bdfg21, SolidityGenerator};
 use summa_solvency::circuits::utils::generate_setup_artifacts;
 use summa_solvency::circuits::{merkle_sum_tree::MstInclusionCircuit, WithInstances};
+use summa_solvency::merkle_sum_tree::utils::calculate_max_root_balance;
 
 const LEVELS: usize = 4;
 const N_CURRENCIES: usize = 2;
@@ -52,20 +53,13 @@ fn save_solidity(name: impl AsRef<str>, solidity: &str) {
     println!("Saved {path}");
 }
 
-// Calculate the maximum value that the Merkle Root can have, given N_BYTES and LEVELS
-fn calculate_max_root_balance(n_bytes: usize, n_levels: usize) -> BigInt {
-    // The max value that can be stored in a leaf node or a sibling node, according to the constraint set in the circuit
-    let max_leaf_value = BigInt::from(2).pow(n_bytes as u32 * 8) - 1;
-    max_leaf_value * (n_levels + 1)
-}
-
 // Given a combination of `N_BYTES` and `LEVELS`, check if there is a risk of overflow in the Merkle Root
 fn is_there_risk_of_overflow(n_bytes: usize, n_levels: usize) -> bool {
     // Calculate the max root balance value
     let max_root_balance = calculate_max_root_balance(n_bytes, n_levels);
 
     // The modulus of the BN256 curve
-    let modulus = BigInt::from_str_radix(&Fp::MODULUS[2..], 16).unwrap();
+    let modulus = BigUint::from_str_radix(&Fp::MODULUS[2..], 16).unwrap();
 
     // Check if the max balance value is greater than the prime
     max_root_balance > modulus
@@ -1,3 +1,4 @@
 pub mod merkle_sum_tree;
 pub mod poseidon;
 pub mod range;
+pub mod utils;
@@ -8,6 +8,16 @@ use halo2_proofs::{
 };
 use std::marker::PhantomData;
 
+/// Number of permutations above which a Poseidon hash is considered expensive, to be compared with `poseidon_permutations` by the callers choosing a Poseidon configuration
+pub const POSEIDON_PERMUTATIONS_WARNING_THRESHOLD: usize = 16;
+
+/// Returns the number of permutations performed by the sponge to hash an input of length `l` with rate `rate`.
+///
+/// The input is absorbed in chunks of `rate` elements and every chunk requires a permutation, so the cost of the hash grows linearly with `l`.
+pub fn poseidon_permutations(l: usize, rate: usize) -> usize {
+    (l + rate - 1) / rate
+}
+
 #[derive(Debug, Clone)]
 
 /// Wrapper structure around Pow5Config which is the Poseidon Hash Configuration from halo2_gadgets.
@@ -86,3 +96,18 @@ impl<S: Spec<Fp, WIDTH, RATE>, const WIDTH: usize, const RATE: usize, const L: u
         hasher.hash(layouter.namespace(|| "hash"), input_cells)
     }
 }
+
+#[cfg(test)]
+mod testing {
+
+    use super::*;
+
+    #[test]
+    fn test_poseidon_permutations() {
+        // With WIDTH = 2, as used by the chips of this crate, every input requires a permutation
+        assert_eq!(poseidon_permutations(6, 1), 6);
+        // With WIDTH = 3, the input is absorbed two elements at a time
+        assert_eq!(poseidon_permutations(6, 2), 3);
+        assert_eq!(poseidon_permutations(7, 2), 4);
+    }
+}
@@ -5,34 +5,179 @@ use crate::chips::poseidon::poseidon_params;
 use halo2_gadgets::poseidon::primitives::*;
 use halo2_proofs::arithmetic::Field;
 use halo2_proofs::halo2curves::bn256::Fr as Fp;
+use std::{fmt, marker::PhantomData};
 
+/// Round counts of the Poseidon permutation.
+///
+/// The defaults are the round counts of the precomputed parameters in `poseidon_params`. Implementors can override them to benchmark alternative parameterizations, in which case the round constants are generated on the fly.
+pub trait PoseidonRounds: fmt::Debug + Clone + Copy {
+    const FULL_ROUNDS: usize = 8;
+    const PARTIAL_ROUNDS: usize = 56;
+}
+
+/// The default round counts, 8 full rounds and 56 partial rounds
+#[derive(Debug, Clone, Copy)]
+pub struct DefaultRounds;
+
+impl PoseidonRounds for DefaultRounds {}
+
+/// Specification for rate 1 Poseidon with the round counts given by `R`
 #[derive(Debug, Clone, Copy)]
-pub struct PoseidonSpec;
+pub struct PoseidonSpecWithRounds<R: PoseidonRounds>(PhantomData<R>);
+
+/// The specification used for every leaf and middle node hash, both off-chain and in the circuits
+pub type PoseidonSpec = PoseidonSpecWithRounds<DefaultRounds>;
 
 pub(crate) type Mds<Fp, const T: usize> = [[Fp; T]; T];
 
-impl Spec<Fp, 2, 1> for PoseidonSpec {
+impl<R: PoseidonRounds> Spec<Fp, 2, 1> for PoseidonSpecWithRounds<R> {
     fn full_rounds() -> usize {
-        8
+        R::FULL_ROUNDS
     }
 
     fn partial_rounds() -> usize {
-        56
+        R::PARTIAL_ROUNDS
     }
 
     fn sbox(val: Fp) -> Fp {
         val.pow_vartime([5])
     }
 
+    // Only used to generate the constants of non default round counts
     fn secure_mds() -> usize {
-        unimplemented!()
+        0
     }
 
     fn constants() -> (Vec<[Fp; 2]>, Mds<Fp, 2>, Mds<Fp, 2>) {
-        (
-            poseidon_params::ROUND_CONSTANTS[..].to_vec(),
-            poseidon_params::MDS,
-            poseidon_params::MDS_INV,
-        )
+        if R::FULL_ROUNDS == DefaultRounds::FULL_ROUNDS
+            && R::PARTIAL_ROUNDS == DefaultRounds::PARTIAL_ROUNDS
+        {
+            (
+                poseidon_params::ROUND_CONSTANTS[..].to_vec(),
+                poseidon_params::MDS,
+                poseidon_params::MDS_INV,
+            )
+        } else {
+            generate_constants::<Fp, Self, 2, 1>()
+        }
+    }
+}
+
+#[cfg(test)]
+mod testing {
+
+    use super::*;
+    use crate::chips::poseidon::hash::{PoseidonChip, PoseidonConfig};
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Fixed, Instance},
+    };
+
+    #[derive(Debug, Clone, Copy)]
+    struct MoreRounds;
+
+    impl PoseidonRounds for MoreRounds {
+        const PARTIAL_ROUNDS: usize = 60;
+    }
+
+    type MoreRoundsSpec = PoseidonSpecWithRounds<MoreRounds>;
+
+    #[derive(Debug, Clone)]
+    struct HashConfig {
+        poseidon_config: PoseidonConfig<2, 1, 2>,
+        advices: [Column<Advice>; 3],
+        instance: Column<Instance>,
+    }
+
+    /// Hashes two private inputs with the `MoreRoundsSpec` specification and exposes the hash as public input
+    #[derive(Default)]
+    struct HashCircuit {
+        inputs: [Fp; 2],
+    }
+
+    impl Circuit<Fp> for HashCircuit {
+        type Config = HashConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let advices: [Column<Advice>; 3] = std::array::from_fn(|_| meta.advice_column());
+            let fixed_columns: [Column<Fixed>; 4] = std::array::from_fn(|_| meta.fixed_column());
+            meta.enable_constant(fixed_columns[2]);
+
+            let poseidon_config = PoseidonChip::<MoreRoundsSpec, 2, 1, 2>::configure(
+                meta,
+                advices[0..2].try_into().unwrap(),
+                advices[2],
+                fixed_columns[0..2].try_into().unwrap(),
+                fixed_columns[2..4].try_into().unwrap(),
+            );
+
+            for col in &advices {
+                meta.enable_equality(*col);
+            }
+
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+
+            HashConfig {
+                poseidon_config,
+                advices,
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let inputs = layouter.assign_region(
+                || "assign inputs",
+                |mut region| {
+                    let left = region.assign_advice(
+                        || "left input",
+                        config.advices[0],
+                        0,
+                        || Value::known(self.inputs[0]),
+                    )?;
+                    let right = region.assign_advice(
+                        || "right input",
+                        config.advices[1],
+                        0,
+                        || Value::known(self.inputs[1]),
+                    )?;
+                    Ok([left, right])
+                },
+            )?;
+
+            let poseidon_chip =
+                PoseidonChip::<MoreRoundsSpec, 2, 1, 2>::construct(config.poseidon_config);
+            let hash = poseidon_chip.hash(layouter.namespace(|| "hash inputs"), inputs)?;
+
+            layouter.constrain_instance(hash.cell(), config.instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_poseidon_spec_with_rounds() {
+        let inputs = [Fp::from(1), Fp::from(2)];
+
+        let hash = Hash::<Fp, MoreRoundsSpec, ConstantLength<2>, 2, 1>::init().hash(inputs);
+        let default_hash = Hash::<Fp, PoseidonSpec, ConstantLength<2>, 2, 1>::init().hash(inputs);
+        assert_ne!(hash, default_hash);
+
+        let circuit = HashCircuit { inputs };
+
+        let valid_prover = MockProver::run(10, &circuit, vec![vec![hash]]).unwrap();
+        valid_prover.assert_satisfied();
+
+        // The hash computed with the default specification doesn't match the circuit
+        let invalid_prover = MockProver::run(10, &circuit, vec![vec![default_hash]]).unwrap();
+        assert!(invalid_prover.verify().is_err());
     }
 }
@@ -1,3 +1,4 @@
 pub mod range_check;
+pub mod tagged_range_check;
 mod tests;
 pub mod utils;
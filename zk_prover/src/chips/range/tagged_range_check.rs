@@ -0,0 +1,208 @@
+use halo2_proofs::arithmetic::Field;
+use halo2_proofs::circuit::{AssignedCell, Layouter, Value};
+use halo2_proofs::halo2curves::bn256::Fr as Fp;
+use halo2_proofs::plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector};
+use halo2_proofs::poly::Rotation;
+
+use std::fmt::Debug;
+
+use super::utils::{decompose_fp_to_bytes, pow_of_two};
+
+/// Configuration for the Tagged Range Check Chip
+///
+/// # Type Parameters
+///
+/// * `N_BITS`: Number of bits in which the value to be checked should lie
+///
+/// # Fields
+///
+/// * `z`: Advice column for the value to be checked and its running sum.
+/// * `full_limb_selector`: Selector to enable the lookup check of an 8-bit limb.
+/// * `partial_limb_selector`: Selector to enable the lookup check of the most significant limb of `N_BITS % 8` bits.
+#[derive(Debug, Copy, Clone)]
+pub struct TaggedRangeCheckConfig<const N_BITS: usize> {
+    z: Column<Advice>,
+    full_limb_selector: Selector,
+    partial_limb_selector: Selector,
+}
+
+/// Helper chip that verifies that the value witnessed in a given cell lies within `N_BITS` bits, where `N_BITS` doesn't need to be a multiple of 8.
+///
+/// The chip performs the same running sum decomposition as `RangeCheckChip`, except that the most significant limb has `N_BITS % 8` bits when `N_BITS` is not a multiple of 8.
+/// The limbs are looked up in a tagged table made of a tag column and a value column, where the rows tagged `w` hold the values from `0` to `2^w - 1`.
+/// A single tagged table serves every limb width it is loaded with, so a 12-bit range check only needs the rows of widths 8 and 4 rather than the `2^12` rows of a contiguous table.
+///
+/// For example, Let's say we want to constraint 0xabc to be within the range N_BITS=12.
+///
+/// * `z(0) = 0xabc`
+/// * `z(1) = (0xabc - 0xbc) / 2^8 = 0xa`
+/// * `z(2) = (0xa - 0xa) / 2^4 = 0x0`
+///
+/// The constraints that are enforced are:
+/// * `(8, z(i) - 2^8⋅z(i+1)) ∈ tagged_table` (enabled by full_limb_selector at offset [0, N_BITS / 8 - 1])
+/// * `(N_BITS % 8, z(i) - 2^(N_BITS % 8)⋅z(i+1)) ∈ tagged_table` (enabled by partial_limb_selector at offset N_BITS / 8, if N_BITS % 8 != 0)
+/// * `z(ceil(N_BITS / 8)) == 0`
+#[derive(Debug, Clone)]
+pub struct TaggedRangeCheckChip<const N_BITS: usize> {
+    config: TaggedRangeCheckConfig<N_BITS>,
+}
+
+impl<const N_BITS: usize> TaggedRangeCheckChip<N_BITS> {
+    const FULL_LIMBS: usize = N_BITS / 8;
+    const PARTIAL_LIMB_BITS: usize = N_BITS % 8;
+
+    pub fn construct(config: TaggedRangeCheckConfig<N_BITS>) -> Self {
+        Self { config }
+    }
+
+    /// Configures the Tagged Range Chip
+    /// Note: the tagged table should be loaded with `load_tagged_table` for the widths 8 and `N_BITS % 8` (if not 0), otherwise the range check will fail.
+    pub fn configure(
+        meta: &mut ConstraintSystem<Fp>,
+        z: Column<Advice>,
+        table_tag: Column<Fixed>,
+        table_value: Column<Fixed>,
+    ) -> TaggedRangeCheckConfig<N_BITS> {
+        let full_limb_selector = meta.complex_selector();
+        let partial_limb_selector = meta.complex_selector();
+
+        meta.annotate_lookup_any_column(table_tag, || "LOOKUP_RANGE_TAG");
+        meta.annotate_lookup_any_column(table_value, || "LOOKUP_RANGE_VALUE");
+
+        meta.lookup_any(
+            "tagged range check for difference between each interstitial running sum output",
+            |meta| {
+                let z_cur = meta.query_advice(z, Rotation::cur());
+                let z_next = meta.query_advice(z, Rotation::next());
+
+                let full_limb = meta.query_selector(full_limb_selector);
+                let partial_limb = meta.query_selector(partial_limb_selector);
+
+                let table_tag = meta.query_fixed(table_tag, Rotation::cur());
+                let table_value = meta.query_fixed(table_value, Rotation::cur());
+
+                // The two selectors are never enabled on the same row, so the tag is the width of the limb of the row, or 0 if none is enabled
+                let tag = full_limb.clone() * Expression::Constant(Fp::from(8))
+                    + partial_limb.clone()
+                        * Expression::Constant(Fp::from(Self::PARTIAL_LIMB_BITS as u64));
+
+                let full_limb_diff =
+                    z_cur.clone() - z_next.clone() * Expression::Constant(Fp::from(1 << 8));
+                let partial_limb_diff =
+                    z_cur - z_next * Expression::Constant(pow_of_two(Self::PARTIAL_LIMB_BITS));
+
+                let value = full_limb * full_limb_diff + partial_limb * partial_limb_diff;
+
+                vec![(tag, table_tag), (value, table_value)]
+            },
+        );
+
+        TaggedRangeCheckConfig {
+            z,
+            full_limb_selector,
+            partial_limb_selector,
+        }
+    }
+
+    /// Loads the tagged table with the rows `(w, 0), ..., (w, 2^w - 1)` for every width `w` of `widths`, preceded by the row `(0, 0)` matched by the rows where no limb is checked.
+    pub fn load_tagged_table(
+        layouter: &mut impl Layouter<Fp>,
+        table_tag: Column<Fixed>,
+        table_value: Column<Fixed>,
+        widths: &[usize],
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || format!("load tagged range check table of widths {:?}", widths),
+            |mut region| {
+                region.assign_fixed(|| "tag", table_tag, 0, || Value::known(Fp::zero()))?;
+                region.assign_fixed(|| "value", table_value, 0, || Value::known(Fp::zero()))?;
+
+                let mut offset = 1;
+                for width in widths {
+                    for value in 0..(1u64 << width) {
+                        region.assign_fixed(
+                            || "tag",
+                            table_tag,
+                            offset,
+                            || Value::known(Fp::from(*width as u64)),
+                        )?;
+                        region.assign_fixed(
+                            || "value",
+                            table_value,
+                            offset,
+                            || Value::known(Fp::from(value)),
+                        )?;
+                        offset += 1;
+                    }
+                }
+
+                Ok(())
+            },
+        )
+    }
+
+    /// Assign the running sum to the chip starting from the value within an assigned cell.
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        value: &AssignedCell<Fp, Fp>,
+    ) -> Result<(), Error> {
+        let n_limbs = Self::FULL_LIMBS + usize::from(Self::PARTIAL_LIMB_BITS != 0);
+
+        layouter.assign_region(
+            || "assign value to perform tagged range check",
+            |mut region| {
+                // enable the lookup of the 8-bit limbs at offset [0, FULL_LIMBS - 1] and of the partial limb at offset FULL_LIMBS
+                for i in 0..Self::FULL_LIMBS {
+                    self.config.full_limb_selector.enable(&mut region, i)?;
+                }
+                if Self::PARTIAL_LIMB_BITS != 0 {
+                    self.config
+                        .partial_limb_selector
+                        .enable(&mut region, Self::FULL_LIMBS)?;
+                }
+
+                // copy `value` to `z_0` at offset 0
+                let mut z = value.copy_advice(
+                    || "assign value to be range checked",
+                    &mut region,
+                    self.config.z,
+                    0,
+                )?;
+
+                // Decompose the value in #n_limbs bytes, the partial limb being the most significant one
+                let limbs = value
+                    .value()
+                    .copied()
+                    .map(|x| decompose_fp_to_bytes(x, n_limbs))
+                    .transpose_vec(n_limbs);
+
+                for (i, limb) in limbs.iter().enumerate() {
+                    let limb_bits = if i < Self::FULL_LIMBS {
+                        8
+                    } else {
+                        Self::PARTIAL_LIMB_BITS
+                    };
+                    let two_pow_limb_bits_inv =
+                        Value::known(pow_of_two(limb_bits).invert().unwrap());
+
+                    // z_next = (z_cur - limb) / (2^limb_bits)
+                    let z_cur_val = z.value().copied();
+                    let limb = limb.map(|limb| Fp::from(limb as u64));
+                    let z_next_val = (z_cur_val - limb) * two_pow_limb_bits_inv;
+                    z = region.assign_advice(
+                        || format!("z_{:?}", i + 1),
+                        self.config.z,
+                        i + 1,
+                        || z_next_val,
+                    )?;
+                }
+
+                // Constrain the final running sum output to be zero.
+                region.constrain_constant(z.cell(), Fp::from(0))?;
+
+                Ok(())
+            },
+        )
+    }
+}
@@ -1,5 +1,8 @@
 use crate::{
-    chips::range::range_check::{RangeCheckChip, RangeCheckConfig},
+    chips::range::{
+        range_check::{RangeCheckChip, RangeCheckConfig},
+        tagged_range_check::{TaggedRangeCheckChip, TaggedRangeCheckConfig},
+    },
     circuits::traits::CircuitBase,
 };
 use halo2_proofs::{
@@ -181,9 +184,84 @@ impl<const N_BYTES: usize> Circuit<Fp> for TestCircuit<N_BYTES> {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct TaggedTestConfig<const N_BITS: usize> {
+    pub value: Column<Advice>,
+    pub tagged_range_check_config: TaggedRangeCheckConfig<N_BITS>,
+    pub table_tag: Column<Fixed>,
+    pub table_value: Column<Fixed>,
+}
+
+// The test circuit takes a single input and performs a range check of N_BITS bits on it, using a tagged table loaded with the widths 8 and N_BITS % 8.
+#[derive(Default, Clone, Debug)]
+struct TaggedTestCircuit<const N_BITS: usize> {
+    pub value: Fp,
+}
+
+impl<const N_BITS: usize> Circuit<Fp> for TaggedTestCircuit<N_BITS> {
+    type Config = TaggedTestConfig<N_BITS>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let z = meta.advice_column();
+        let value = meta.advice_column();
+        let table_tag = meta.fixed_column();
+        let table_value = meta.fixed_column();
+
+        meta.enable_equality(z);
+        meta.enable_equality(value);
+
+        let constants = meta.fixed_column();
+        meta.enable_constant(constants);
+
+        let tagged_range_check_config =
+            TaggedRangeCheckChip::<N_BITS>::configure(meta, z, table_tag, table_value);
+
+        TaggedTestConfig {
+            value,
+            tagged_range_check_config,
+            table_tag,
+            table_value,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let value_cell = layouter.assign_region(
+            || "assign value",
+            |mut region| {
+                region.assign_advice(|| "value", config.value, 0, || Value::known(self.value))
+            },
+        )?;
+
+        // Load the tagged table with the widths of the limbs
+        TaggedRangeCheckChip::<N_BITS>::load_tagged_table(
+            &mut layouter,
+            config.table_tag,
+            config.table_value,
+            &[8, N_BITS % 8],
+        )?;
+
+        let tagged_range_chip = TaggedRangeCheckChip::construct(config.tagged_range_check_config);
+        tagged_range_chip.assign(
+            layouter.namespace(|| "checking value is in range"),
+            &value_cell,
+        )?;
+
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod testing {
-    use super::TestCircuit;
+    use super::{TaggedTestCircuit, TestCircuit};
     use halo2_proofs::{
         dev::{FailureLocation, MockProver, VerifyFailure},
         halo2curves::bn256::Fr as Fp,
@@ -272,6 +350,35 @@ mod testing {
         );
     }
 
+    // value = 0xfff is the max value within 12 bits
+    // The tagged table only holds the 256 + 16 rows of widths 8 and 4, so the circuit fits in 2^9 rows
+    #[test]
+    fn test_tagged_none_overflow_12bits() {
+        let k = 9;
+
+        for value in [0, 0xabc, 0xfff] {
+            let circuit = TaggedTestCircuit::<12> {
+                value: Fp::from(value),
+            };
+            let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+            prover.assert_satisfied();
+        }
+    }
+
+    // value = 0x1000 overflows 12 bits, its most significant limb 0x10 is not in the rows of width 4
+    #[test]
+    fn test_tagged_overflow_12bits() {
+        let k = 9;
+
+        for value in [0x1000, 0xffff, 0x10000] {
+            let circuit = TaggedTestCircuit::<12> {
+                value: Fp::from(value),
+            };
+            let invalid_prover = MockProver::run(k, &circuit, vec![]).unwrap();
+            assert!(invalid_prover.verify().is_err());
+        }
+    }
+
     #[cfg(feature = "dev-graph")]
     #[test]
     fn print_range_check_test() {
@@ -0,0 +1,93 @@
+use halo2_proofs::{
+    circuit::{AssignedCell, Region},
+    halo2curves::bn256::Fr as Fp,
+    plonk::{Advice, Column, Error, Instance},
+};
+
+/// Copies the value at `row` of the `instance` column into the `advice` column of `region` at `offset`.
+///
+/// The assigned cell is constrained to be equal to the instance value, so both columns must have equality enabled.
+pub fn copy_instance_to_advice(
+    region: &mut Region<'_, Fp>,
+    instance: Column<Instance>,
+    row: usize,
+    advice: Column<Advice>,
+    offset: usize,
+) -> Result<AssignedCell<Fp, Fp>, Error> {
+    region.assign_advice_from_instance(
+        || format!("copy instance row {}", row),
+        instance,
+        row,
+        advice,
+        offset,
+    )
+}
+
+#[cfg(test)]
+mod testing {
+
+    use super::*;
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::MockProver,
+        plonk::{Circuit, ConstraintSystem},
+    };
+
+    #[derive(Debug, Clone)]
+    struct CopyInstanceConfig {
+        advice: Column<Advice>,
+        instance: Column<Instance>,
+    }
+
+    /// Copies the first instance value into an advice cell and exposes the copied cell as the second instance value
+    #[derive(Default)]
+    struct CopyInstanceCircuit;
+
+    impl Circuit<Fp> for CopyInstanceCircuit {
+        type Config = CopyInstanceConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let advice = meta.advice_column();
+            let instance = meta.instance_column();
+
+            meta.enable_equality(advice);
+            meta.enable_equality(instance);
+
+            CopyInstanceConfig { advice, instance }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let copied = layouter.assign_region(
+                || "copy instance",
+                |mut region| {
+                    copy_instance_to_advice(&mut region, config.instance, 0, config.advice, 0)
+                },
+            )?;
+
+            layouter.constrain_instance(copied.cell(), config.instance, 1)
+        }
+    }
+
+    #[test]
+    fn test_copy_instance_to_advice() {
+        let value = Fp::from(42);
+
+        let valid_prover =
+            MockProver::run(4, &CopyInstanceCircuit, vec![vec![value, value]]).unwrap();
+        valid_prover.assert_satisfied();
+
+        // The copied cell doesn't match the second instance value
+        let invalid_prover =
+            MockProver::run(4, &CopyInstanceCircuit, vec![vec![value, Fp::from(43)]]).unwrap();
+        assert!(invalid_prover.verify().is_err());
+    }
+}
@@ -0,0 +1,196 @@
+use crate::chips::merkle_sum_tree::MerkleSumTreeChip;
+use crate::chips::range::range_check::RangeCheckChip;
+use crate::circuits::merkle_sum_tree::{MstInclusionCircuit, MstInclusionConfig};
+use crate::circuits::traits::CircuitBase;
+use crate::circuits::WithInstances;
+use crate::merkle_sum_tree::MerkleProof;
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner};
+use halo2_proofs::halo2curves::bn256::Fr as Fp;
+use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error};
+
+/// Circuit for verifying that the balances of a user didn't decrease between two snapshots of the merkle sum tree, without revealing the balances.
+///
+/// The circuit verifies the inclusion of the old entry inside the old merkle sum tree and the inclusion of the new entry inside the new merkle sum tree, enforces that both entries share the same username and that, for each currency, `new_balance - old_balance` lies within the range defined by N_BYTES, namely that `new_balance >= old_balance`.
+///
+/// # Type Parameters
+///
+/// * `LEVELS`: The number of levels of both merkle sum trees.
+/// * `N_CURRENCIES`: The number of currencies for which the solvency is verified.
+/// * `N_BYTES`: The number of bytes in which the balances and the balance deltas should lie
+///
+/// # Fields
+///
+/// * `old_inclusion`: The inclusion circuit of the user entry inside the old merkle sum tree
+/// * `new_inclusion`: The inclusion circuit of the user entry inside the new merkle sum tree
+#[derive(Clone)]
+pub struct BalanceDeltaCircuit<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    pub old_inclusion: MstInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES>,
+    pub new_inclusion: MstInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES>,
+}
+
+impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize> WithInstances
+    for BalanceDeltaCircuit<LEVELS, N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    /// Returns the number of public inputs of the circuit. It is {2 * (2 + N_CURRENCIES)}, namely the public inputs of the old inclusion followed by the public inputs of the new inclusion.
+    fn num_instances(&self) -> usize {
+        2 * (2 + N_CURRENCIES)
+    }
+    /// Returns the values of the public inputs of the circuit. Namely the old leaf hash, the old root hash and the old root balances followed by the new leaf hash, the new root hash and the new root balances.
+    fn instances(&self) -> Vec<Vec<Fp>> {
+        let mut instance = self.old_inclusion.instances()[0].clone();
+        instance.extend_from_slice(&self.new_inclusion.instances()[0]);
+        vec![instance]
+    }
+}
+
+impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize> CircuitBase
+    for BalanceDeltaCircuit<LEVELS, N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+}
+
+impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize>
+    BalanceDeltaCircuit<LEVELS, N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    pub fn init_empty() -> Self {
+        Self {
+            old_inclusion: MstInclusionCircuit::init_empty(),
+            new_inclusion: MstInclusionCircuit::init_empty(),
+        }
+    }
+
+    /// Initializes the circuit with the merkle proofs of the user entry inside the old and the new merkle sum tree.
+    pub fn init(
+        old_merkle_proof: MerkleProof<N_CURRENCIES>,
+        new_merkle_proof: MerkleProof<N_CURRENCIES>,
+    ) -> Self {
+        Self {
+            old_inclusion: MstInclusionCircuit::init(old_merkle_proof),
+            new_inclusion: MstInclusionCircuit::init(new_merkle_proof),
+        }
+    }
+}
+
+impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize> Circuit<Fp>
+    for BalanceDeltaCircuit<LEVELS, N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    type Config = MstInclusionConfig<N_CURRENCIES, N_BYTES>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::init_empty()
+    }
+
+    /// Configures the circuit. The configuration is shared with the Mst Inclusion circuit
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        MstInclusionConfig::<N_CURRENCIES, N_BYTES>::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let merkle_sum_tree_chip =
+            MerkleSumTreeChip::<N_CURRENCIES>::construct(config.merkle_sum_tree_config.clone());
+
+        let range_check_chip = RangeCheckChip::<N_BYTES>::construct(config.range_check_config);
+
+        // load lookup table for range check, shared by both inclusions and the balance deltas
+        self.load(&mut layouter, config.fixed_columns[4])?;
+
+        let mut exposed_cells = vec![];
+        let mut usernames = vec![];
+        let mut leaves_balances = vec![];
+
+        // Verify the inclusion of the user entry inside the old and the new merkle sum tree
+        for inclusion in [&self.old_inclusion, &self.new_inclusion] {
+            let (username, leaf_balances, leaf_hash) =
+                inclusion.assign_entry(&mut layouter, &config)?;
+
+            let (root_hash, root_balances) = inclusion.assign_path(
+                &mut layouter,
+                &config,
+                leaf_hash.clone(),
+                leaf_balances.clone(),
+            )?;
+
+            exposed_cells.push(leaf_hash);
+            exposed_cells.push(root_hash);
+            exposed_cells.extend(root_balances);
+
+            usernames.push(username);
+            leaves_balances.push(leaf_balances);
+        }
+
+        // Both entries must belong to the same user
+        layouter.assign_region(
+            || "enforce same username",
+            |mut region| region.constrain_equal(usernames[0].cell(), usernames[1].cell()),
+        )?;
+
+        for currency in 0..N_CURRENCIES {
+            let old_balance = &leaves_balances[0][currency];
+            let new_balance = &leaves_balances[1][currency];
+
+            // Assign the balance delta `new_balance - old_balance` to the witness
+            let delta = layouter.assign_region(
+                || format!("assign balance delta {}", currency),
+                |mut region| {
+                    region.assign_advice(
+                        || "balance delta",
+                        config.advices[1],
+                        0,
+                        || new_balance.value().copied() - old_balance.value().copied(),
+                    )
+                },
+            )?;
+
+            // Constrain `old_balance + delta = new_balance`
+            let computed_new_balance = merkle_sum_tree_chip.sum_balances_per_level(
+                layouter.namespace(|| format!("currency {}: sum balance delta", currency)),
+                old_balance,
+                &delta,
+            )?;
+
+            layouter.assign_region(
+                || format!("currency {}: enforce new balance", currency),
+                |mut region| {
+                    region.constrain_equal(computed_new_balance.cell(), new_balance.cell())
+                },
+            )?;
+
+            // A negative delta wraps around the field and fails the range check
+            range_check_chip.assign(
+                layouter.namespace(|| format!("currency {}: range check balance delta", currency)),
+                &delta,
+            )?;
+        }
+
+        for (row, cell) in exposed_cells.iter().enumerate() {
+            self.expose_public(
+                layouter.namespace(|| format!("public input {}", row)),
+                cell,
+                row,
+                config.instance,
+            )?;
+        }
+
+        Ok(())
+    }
+}
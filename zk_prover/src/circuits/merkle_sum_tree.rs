@@ -83,179 +83,93 @@ where
     }
 
     /// Initializes the circuit with the merkle proof and the entry of the user of which the inclusion is to be verified.
+    ///
+    /// Panics if the depth of the merkle proof doesn't match `LEVELS`. Use `try_init` to handle the error instead.
     pub fn init(merkle_proof: MerkleProof<N_CURRENCIES>) -> Self
     where
         [usize; N_CURRENCIES + 1]: Sized,
         [usize; N_CURRENCIES + 2]: Sized,
     {
-        assert_eq!(merkle_proof.path_indices.len(), LEVELS);
-        assert_eq!(
-            merkle_proof.sibling_middle_node_hash_preimages.len(),
-            LEVELS - 1
-        );
-        Self {
+        Self::try_init(merkle_proof).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Initializes the circuit with the merkle proof and the entry of the user of which the inclusion is to be verified.
+    ///
+    /// Returns an error if the depth of the tree the merkle proof was generated from doesn't match `LEVELS`, rather than failing later with a constraint error.
+    pub fn try_init(merkle_proof: MerkleProof<N_CURRENCIES>) -> Result<Self, &'static str>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        if merkle_proof.path_indices.len() != LEVELS
+            || merkle_proof.sibling_middle_node_hash_preimages.len() != LEVELS - 1
+        {
+            return Err("The depth of the merkle proof doesn't match the LEVELS of the circuit");
+        }
+
+        Ok(Self {
             entry: merkle_proof.entry,
             path_indices: merkle_proof.path_indices,
             sibling_leaf_node_hash_preimage: merkle_proof.sibling_leaf_node_hash_preimage,
             sibling_middle_node_hash_preimages: merkle_proof.sibling_middle_node_hash_preimages,
             root: merkle_proof.root,
-        }
+        })
     }
-}
-
-/// Configuration for the Mst Inclusion circuit
-/// # Type Parameters
-///
-/// * `N_CURRENCIES`: The number of currencies for which the solvency is verified.
-/// * `N_BYTES`: The number of bytes in which the balances should lie
-///
-/// # Fields
-///
-/// * `merkle_sum_tree_config`: Configuration for the merkle sum tree
-/// * `poseidon_entry_config`: Configuration for the poseidon hash function with WIDTH = 2 and RATE = 1 and input length of N_CURRENCIES + 1. Needed to perform the hashing from the entry to the leaf.
-/// * `poseidon_middle_config`: Configuration for the poseidon hash function with WIDTH = 2 and RATE = 1 and input length of N_CURRENCIES + 2. Needed to perform hashings from the leaf to the root.
-/// * `range_check_config`: Configuration for the range check chip
-/// * `instance`: Instance column used to store the public inputs
-/// * `advices`: Advice columns used to store the private inputs
-
-#[derive(Debug, Clone)]
-pub struct MstInclusionConfig<const N_CURRENCIES: usize, const N_BYTES: usize>
-where
-    [usize; N_CURRENCIES + 1]: Sized,
-    [usize; N_CURRENCIES + 2]: Sized,
-{
-    merkle_sum_tree_config: MerkleSumTreeConfig,
-    poseidon_entry_config: PoseidonConfig<2, 1, { N_CURRENCIES + 1 }>,
-    poseidon_middle_config: PoseidonConfig<2, 1, { N_CURRENCIES + 2 }>,
-    range_check_config: RangeCheckConfig<N_BYTES>,
-    instance: Column<Instance>,
-    advices: [Column<Advice>; 3],
-    fixed_columns: [Column<Fixed>; 5],
-}
-
-impl<const N_CURRENCIES: usize, const N_BYTES: usize> MstInclusionConfig<N_CURRENCIES, N_BYTES>
-where
-    [usize; N_CURRENCIES + 1]: Sized,
-    [usize; N_CURRENCIES + 2]: Sized,
-{
-    pub fn configure(meta: &mut ConstraintSystem<Fp>) -> Self {
-        // the max number of advices columns needed is WIDTH + 1 given requirement of the poseidon config
-        let advices: [Column<Advice>; 3] = std::array::from_fn(|_| meta.advice_column());
-
-        // we need 2 * WIDTH fixed columns for poseidon config + 1 for the range check chip
-        let fixed_columns: [Column<Fixed>; 5] = std::array::from_fn(|_| meta.fixed_column());
-
-        // we also need 2 selectors for the MerkleSumTreeChip
-        let selectors: [Selector; 2] = std::array::from_fn(|_| meta.selector());
-
-        // we need 1 complex selector for the lookup check in the range check chip
-        let enable_lookup_selector = meta.complex_selector();
-
-        // enable constant for the fixed_column[2], this is required for the poseidon chip and the range check chip
-        meta.enable_constant(fixed_columns[2]);
-
-        let poseidon_entry_config =
-            PoseidonChip::<PoseidonSpec, 2, 1, { N_CURRENCIES + 1 }>::configure(
-                meta,
-                advices[0..2].try_into().unwrap(),
-                advices[2],
-                fixed_columns[0..2].try_into().unwrap(),
-                fixed_columns[2..4].try_into().unwrap(),
-            );
-
-        // in fact, the poseidon config requires #WIDTH advice columns for state and 1 for partial_sbox, #WIDTH fixed columns for rc_a and #WIDTH for rc_b
-        let poseidon_middle_config =
-            PoseidonChip::<PoseidonSpec, 2, 1, { N_CURRENCIES + 2 }>::configure(
-                meta,
-                advices[0..2].try_into().unwrap(),
-                advices[2],
-                fixed_columns[0..2].try_into().unwrap(),
-                fixed_columns[2..4].try_into().unwrap(),
-            );
 
-        // enable permutation for all the advice columns
-        for col in &advices {
-            meta.enable_equality(*col);
+    /// Initializes the circuit from the merkle proof of the user at `index` alone, e.g. received by a light client that doesn't hold the tree.
+    ///
+    /// Returns an error if the path indices of the merkle proof don't lead to the leaf at `index`, or if the depth of the merkle proof doesn't match `LEVELS`.
+    pub fn init_from_proof(
+        merkle_proof: &MerkleProof<N_CURRENCIES>,
+        index: usize,
+    ) -> Result<Self, &'static str>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        if index >= 1 << merkle_proof.path_indices.len()
+            || merkle_proof
+                .path_indices
+                .iter()
+                .enumerate()
+                .any(|(level, path_index)| *path_index != Fp::from(((index >> level) & 1) as u64))
+        {
+            return Err("The path indices of the merkle proof don't match the index of the user");
         }
 
-        // the configuration of merkle_sum_tree will always require 3 advices, no matter the number of currencies
-        let merkle_sum_tree_config = MerkleSumTreeChip::<N_CURRENCIES>::configure(
-            meta,
-            advices[0..3].try_into().unwrap(),
-            selectors[0..2].try_into().unwrap(),
-        );
-
-        let range_check_config = RangeCheckChip::<N_BYTES>::configure(
-            meta,
-            advices[0],
-            fixed_columns[4],
-            enable_lookup_selector,
-        );
-
-        let instance = meta.instance_column();
-        meta.enable_equality(instance);
-
-        Self {
-            merkle_sum_tree_config,
-            poseidon_entry_config,
-            poseidon_middle_config,
-            range_check_config,
-            instance,
-            advices,
-            fixed_columns,
-        }
+        Self::try_init(merkle_proof.clone())
     }
-}
 
-impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize> Circuit<Fp>
-    for MstInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES>
-where
-    [usize; N_CURRENCIES + 1]: Sized,
-    [usize; N_CURRENCIES + 2]: Sized,
-{
-    type Config = MstInclusionConfig<N_CURRENCIES, N_BYTES>;
-    type FloorPlanner = SimpleFloorPlanner;
-
-    fn without_witnesses(&self) -> Self {
-        Self::init_empty()
-    }
-
-    /// Configures the circuit
-    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
-        MstInclusionConfig::<N_CURRENCIES, N_BYTES>::configure(meta)
-    }
-
-    fn synthesize(
+    /// Assigns the entry username and balances to the witness and performs the hashing to obtain the leaf hash.
+    ///
+    /// Returns the assigned username, the assigned balances and the leaf hash.
+    pub(crate) fn assign_entry(
         &self,
-        config: Self::Config,
-        mut layouter: impl Layouter<Fp>,
-    ) -> Result<(), Error> {
-        // build auxiliary chips
-        let merkle_sum_tree_chip =
-            MerkleSumTreeChip::<N_CURRENCIES>::construct(config.merkle_sum_tree_config);
-
+        layouter: &mut impl Layouter<Fp>,
+        config: &MstInclusionConfig<N_CURRENCIES, N_BYTES>,
+    ) -> Result<
+        (
+            AssignedCell<Fp, Fp>,
+            Vec<AssignedCell<Fp, Fp>>,
+            AssignedCell<Fp, Fp>,
+        ),
+        Error,
+    > {
         let poseidon_entry_chip =
             PoseidonChip::<PoseidonSpec, 2, 1, { N_CURRENCIES + 1 }>::construct(
-                config.poseidon_entry_config,
+                config.poseidon_entry_config.clone(),
             );
 
-        let poseidon_middle_chip =
-            PoseidonChip::<PoseidonSpec, 2, 1, { N_CURRENCIES + 2 }>::construct(
-                config.poseidon_middle_config,
-            );
-
-        let range_check_chip = RangeCheckChip::<N_BYTES>::construct(config.range_check_config);
-
         // Assign the entry username to the witness
         let username = self.assign_value_to_witness(
             layouter.namespace(|| "assign entry username"),
-            big_uint_to_fp(&self.entry.username_as_big_uint()),
+            big_uint_to_fp(&self.entry.leaf_identity()),
             "entry username",
             config.advices[0],
         )?;
 
         // Assign the entry balances to the witness
-        let mut current_balances = vec![];
+        let mut balances = vec![];
 
         for i in 0..N_CURRENCIES {
             let balance = self.assign_value_to_witness(
@@ -264,14 +178,14 @@ where
                 "entry balance",
                 config.advices[1],
             )?;
-            current_balances.push(balance);
+            balances.push(balance);
         }
 
         // Perform the hashing to username and balances to obtain the leaf hash
         // create an hash_input array of length N_CURRENCIES + 1 that contains the entry username and the entry balances
-        let entry_hasher_input_vec: Vec<AssignedCell<Fp, Fp>> = [username]
+        let entry_hasher_input_vec: Vec<AssignedCell<Fp, Fp>> = [username.clone()]
             .iter()
-            .chain(current_balances.iter())
+            .chain(balances.iter())
             .map(|x| x.to_owned())
             .collect();
 
@@ -282,21 +196,43 @@ where
             };
 
         // compute the entry hash
-        let mut current_hash = poseidon_entry_chip.hash(
+        let leaf_hash = poseidon_entry_chip.hash(
             layouter.namespace(|| "perform poseidon entry hash"),
             entry_hasher_input,
         )?;
 
-        // expose the first current hash, namely the leaf hash, as public input
-        self.expose_public(
-            layouter.namespace(|| "public leaf hash"),
-            &current_hash,
-            0,
-            config.instance,
-        )?;
+        Ok((username, balances, leaf_hash))
+    }
 
-        // load lookup table for range check
-        self.load(&mut layouter, config.fixed_columns[4])?;
+    /// Performs the hashing and the balance sums from the leaf up to the root of the merkle sum tree following the merkle proof.
+    /// The leaf balances and the sibling balances are range checked. The lookup table for the range check must be loaded by the caller.
+    ///
+    /// Returns the root hash and the root balances.
+    pub(crate) fn assign_path(
+        &self,
+        layouter: &mut impl Layouter<Fp>,
+        config: &MstInclusionConfig<N_CURRENCIES, N_BYTES>,
+        leaf_hash: AssignedCell<Fp, Fp>,
+        leaf_balances: Vec<AssignedCell<Fp, Fp>>,
+    ) -> Result<(AssignedCell<Fp, Fp>, Vec<AssignedCell<Fp, Fp>>), Error> {
+        // build auxiliary chips
+        let merkle_sum_tree_chip =
+            MerkleSumTreeChip::<N_CURRENCIES>::construct(config.merkle_sum_tree_config.clone());
+
+        let poseidon_entry_chip =
+            PoseidonChip::<PoseidonSpec, 2, 1, { N_CURRENCIES + 1 }>::construct(
+                config.poseidon_entry_config.clone(),
+            );
+
+        let poseidon_middle_chip =
+            PoseidonChip::<PoseidonSpec, 2, 1, { N_CURRENCIES + 2 }>::construct(
+                config.poseidon_middle_config.clone(),
+            );
+
+        let range_check_chip = RangeCheckChip::<N_BYTES>::construct(config.range_check_config);
+
+        let mut current_hash = leaf_hash;
+        let mut current_balances = leaf_balances;
 
         for level in 0..LEVELS {
             let namespace_prefix = format!("level {}", level);
@@ -460,6 +396,7 @@ where
             let mut next_balances = vec![];
 
             // For every level, perform sum the balances `current_balances` and `sibling_balances`
+            // The sums are the balances of the next node hash preimage, so the balances of the sibling preimage are bound both to the sibling hash and to the balances of the parent node. A sibling preimage with doctored balances changes the root hash and the root balances
             for currency in 0..N_CURRENCIES {
                 let next_balance = merkle_sum_tree_chip.sum_balances_per_level(
                     layouter.namespace(|| {
@@ -499,16 +436,165 @@ where
             current_hash = computed_hash;
         }
 
+        Ok((current_hash, current_balances))
+    }
+}
+
+/// Configuration for the Mst Inclusion circuit
+/// # Type Parameters
+///
+/// * `N_CURRENCIES`: The number of currencies for which the solvency is verified.
+/// * `N_BYTES`: The number of bytes in which the balances should lie
+///
+/// # Fields
+///
+/// * `merkle_sum_tree_config`: Configuration for the merkle sum tree
+/// * `poseidon_entry_config`: Configuration for the poseidon hash function with WIDTH = 2 and RATE = 1 and input length of N_CURRENCIES + 1. Needed to perform the hashing from the entry to the leaf.
+/// * `poseidon_middle_config`: Configuration for the poseidon hash function with WIDTH = 2 and RATE = 1 and input length of N_CURRENCIES + 2. Needed to perform hashings from the leaf to the root.
+/// * `range_check_config`: Configuration for the range check chip
+/// * `instance`: Instance column used to store the public inputs
+/// * `advices`: Advice columns used to store the private inputs
+
+#[derive(Debug, Clone)]
+pub struct MstInclusionConfig<const N_CURRENCIES: usize, const N_BYTES: usize>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    pub(crate) merkle_sum_tree_config: MerkleSumTreeConfig,
+    pub(crate) poseidon_entry_config: PoseidonConfig<2, 1, { N_CURRENCIES + 1 }>,
+    pub(crate) poseidon_middle_config: PoseidonConfig<2, 1, { N_CURRENCIES + 2 }>,
+    pub(crate) range_check_config: RangeCheckConfig<N_BYTES>,
+    pub(crate) instance: Column<Instance>,
+    pub(crate) advices: [Column<Advice>; 3],
+    pub(crate) fixed_columns: [Column<Fixed>; 5],
+}
+
+impl<const N_CURRENCIES: usize, const N_BYTES: usize> MstInclusionConfig<N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    pub fn configure(meta: &mut ConstraintSystem<Fp>) -> Self {
+        // the max number of advices columns needed is WIDTH + 1 given requirement of the poseidon config
+        let advices: [Column<Advice>; 3] = std::array::from_fn(|_| meta.advice_column());
+
+        // we need 2 * WIDTH fixed columns for poseidon config + 1 for the range check chip
+        let fixed_columns: [Column<Fixed>; 5] = std::array::from_fn(|_| meta.fixed_column());
+
+        // we also need 2 selectors for the MerkleSumTreeChip
+        let selectors: [Selector; 2] = std::array::from_fn(|_| meta.selector());
+
+        // we need 1 complex selector for the lookup check in the range check chip
+        let enable_lookup_selector = meta.complex_selector();
+
+        // enable constant for the fixed_column[2], this is required for the poseidon chip and the range check chip
+        meta.enable_constant(fixed_columns[2]);
+
+        let poseidon_entry_config =
+            PoseidonChip::<PoseidonSpec, 2, 1, { N_CURRENCIES + 1 }>::configure(
+                meta,
+                advices[0..2].try_into().unwrap(),
+                advices[2],
+                fixed_columns[0..2].try_into().unwrap(),
+                fixed_columns[2..4].try_into().unwrap(),
+            );
+
+        // in fact, the poseidon config requires #WIDTH advice columns for state and 1 for partial_sbox, #WIDTH fixed columns for rc_a and #WIDTH for rc_b
+        let poseidon_middle_config =
+            PoseidonChip::<PoseidonSpec, 2, 1, { N_CURRENCIES + 2 }>::configure(
+                meta,
+                advices[0..2].try_into().unwrap(),
+                advices[2],
+                fixed_columns[0..2].try_into().unwrap(),
+                fixed_columns[2..4].try_into().unwrap(),
+            );
+
+        // enable permutation for all the advice columns
+        for col in &advices {
+            meta.enable_equality(*col);
+        }
+
+        // the configuration of merkle_sum_tree will always require 3 advices, no matter the number of currencies
+        let merkle_sum_tree_config = MerkleSumTreeChip::<N_CURRENCIES>::configure(
+            meta,
+            advices[0..3].try_into().unwrap(),
+            selectors[0..2].try_into().unwrap(),
+        );
+
+        let range_check_config = RangeCheckChip::<N_BYTES>::configure(
+            meta,
+            advices[0],
+            fixed_columns[4],
+            enable_lookup_selector,
+        );
+
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        Self {
+            merkle_sum_tree_config,
+            poseidon_entry_config,
+            poseidon_middle_config,
+            range_check_config,
+            instance,
+            advices,
+            fixed_columns,
+        }
+    }
+}
+
+impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize> Circuit<Fp>
+    for MstInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    type Config = MstInclusionConfig<N_CURRENCIES, N_BYTES>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::init_empty()
+    }
+
+    /// Configures the circuit
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        MstInclusionConfig::<N_CURRENCIES, N_BYTES>::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        // Assign the entry and compute the leaf hash
+        let (_, leaf_balances, leaf_hash) = self.assign_entry(&mut layouter, &config)?;
+
+        // expose the first current hash, namely the leaf hash, as public input
+        self.expose_public(
+            layouter.namespace(|| "public leaf hash"),
+            &leaf_hash,
+            0,
+            config.instance,
+        )?;
+
+        // load lookup table for range check
+        self.load(&mut layouter, config.fixed_columns[4])?;
+
+        // Compute the root hash and the root balances from the leaf up the merkle path
+        let (root_hash, root_balances) =
+            self.assign_path(&mut layouter, &config, leaf_hash, leaf_balances)?;
+
         // expose the last current hash, namely the root hash, as public input
         self.expose_public(
             layouter.namespace(|| "public root hash"),
-            &current_hash,
+            &root_hash,
             1,
             config.instance,
         )?;
 
         // expose the last current balances, namely the root balances, as public input
-        for (i, balance) in current_balances.iter().enumerate() {
+        for (i, balance) in root_balances.iter().enumerate() {
             self.expose_public(
                 layouter.namespace(|| format!("public root balance {}", i)),
                 balance,
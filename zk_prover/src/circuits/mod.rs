@@ -1,8 +1,12 @@
+pub mod balance_delta;
 pub mod merkle_sum_tree;
+pub mod subset_sum;
+pub mod subtree_solvency;
 mod tests;
 pub mod traits;
 pub mod types;
 pub mod utils;
+pub mod zero_balance;
 
 use halo2_proofs::halo2curves::bn256::Fr as Fp;
 
@@ -0,0 +1,219 @@
+use crate::chips::merkle_sum_tree::MerkleSumTreeChip;
+use crate::circuits::merkle_sum_tree::{MstInclusionCircuit, MstInclusionConfig};
+use crate::circuits::traits::CircuitBase;
+use crate::circuits::WithInstances;
+use crate::merkle_sum_tree::utils::big_uint_to_fp;
+use crate::merkle_sum_tree::MerkleProof;
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner};
+use halo2_proofs::halo2curves::bn256::Fr as Fp;
+use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error};
+use num_bigint::BigUint;
+
+/// Circuit for verifying that the sum of the balances of a subset of users of the merkle sum tree equals a declared value.
+///
+/// The circuit verifies the inclusion of each user entry inside the merkle sum tree, enforces that all the inclusions are anchored to the same root and that, for each currency, the sum of the balances of the users equals the declared sum.
+/// The leaf hashes of the users are exposed as public inputs, so that the verifier can check that they belong to distinct users.
+///
+/// # Type Parameters
+///
+/// * `LEVELS`: The number of levels of the merkle sum tree
+/// * `N_CURRENCIES`: The number of currencies for which the solvency is verified.
+/// * `N_BYTES`: The number of bytes in which the balances should lie
+/// * `N_USERS`: The number of users in the subset
+///
+/// # Fields
+///
+/// * `inclusions`: The inclusion circuits of the user entries inside the merkle sum tree
+/// * `declared_sums`: The declared sum of the balances of the users for each currency
+#[derive(Clone)]
+pub struct SubsetSumCircuit<
+    const LEVELS: usize,
+    const N_CURRENCIES: usize,
+    const N_BYTES: usize,
+    const N_USERS: usize,
+> where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    pub inclusions: Vec<MstInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES>>,
+    pub declared_sums: [Fp; N_CURRENCIES],
+}
+
+impl<
+        const LEVELS: usize,
+        const N_CURRENCIES: usize,
+        const N_BYTES: usize,
+        const N_USERS: usize,
+    > WithInstances for SubsetSumCircuit<LEVELS, N_CURRENCIES, N_BYTES, N_USERS>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    /// Returns the number of public inputs of the circuit. It is {N_USERS + 1 + 2 * N_CURRENCIES}, namely the leaf hashes of the users, the root hash, the root balances and the declared sums.
+    fn num_instances(&self) -> usize {
+        N_USERS + 1 + 2 * N_CURRENCIES
+    }
+    /// Returns the values of the public inputs of the circuit. Namely the leaf hashes of the users followed by the root hash, the root balances and the declared sums.
+    fn instances(&self) -> Vec<Vec<Fp>> {
+        let mut instance: Vec<Fp> = self
+            .inclusions
+            .iter()
+            .map(|inclusion| inclusion.instances()[0][0])
+            .collect();
+        instance.extend_from_slice(&self.inclusions[0].instances()[0][1..]);
+        instance.extend_from_slice(&self.declared_sums);
+        vec![instance]
+    }
+}
+
+impl<
+        const LEVELS: usize,
+        const N_CURRENCIES: usize,
+        const N_BYTES: usize,
+        const N_USERS: usize,
+    > CircuitBase for SubsetSumCircuit<LEVELS, N_CURRENCIES, N_BYTES, N_USERS>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+}
+
+impl<
+        const LEVELS: usize,
+        const N_CURRENCIES: usize,
+        const N_BYTES: usize,
+        const N_USERS: usize,
+    > SubsetSumCircuit<LEVELS, N_CURRENCIES, N_BYTES, N_USERS>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    pub fn init_empty() -> Self {
+        Self {
+            inclusions: (0..N_USERS)
+                .map(|_| MstInclusionCircuit::init_empty())
+                .collect(),
+            declared_sums: [Fp::zero(); N_CURRENCIES],
+        }
+    }
+
+    /// Initializes the circuit with the merkle proofs of the user entries inside the merkle sum tree and the declared sum of their balances for each currency.
+    ///
+    /// Returns an error if `N_USERS` is 0, if the number of merkle proofs isn't `N_USERS` or if the depth of any merkle proof doesn't match `LEVELS`.
+    pub fn init(
+        merkle_proofs: Vec<MerkleProof<N_CURRENCIES>>,
+        declared_sums: [BigUint; N_CURRENCIES],
+    ) -> Result<Self, &'static str> {
+        if N_USERS == 0 {
+            return Err("The subset must contain at least one user");
+        }
+
+        if merkle_proofs.len() != N_USERS {
+            return Err("The number of merkle proofs doesn't match N_USERS");
+        }
+
+        Ok(Self {
+            inclusions: merkle_proofs
+                .into_iter()
+                .map(MstInclusionCircuit::try_init)
+                .collect::<Result<Vec<_>, _>>()?,
+            declared_sums: declared_sums.map(|sum| big_uint_to_fp(&sum)),
+        })
+    }
+}
+
+impl<
+        const LEVELS: usize,
+        const N_CURRENCIES: usize,
+        const N_BYTES: usize,
+        const N_USERS: usize,
+    > Circuit<Fp> for SubsetSumCircuit<LEVELS, N_CURRENCIES, N_BYTES, N_USERS>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    type Config = MstInclusionConfig<N_CURRENCIES, N_BYTES>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::init_empty()
+    }
+
+    /// Configures the circuit. The configuration is shared with the Mst Inclusion circuit
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        MstInclusionConfig::<N_CURRENCIES, N_BYTES>::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let merkle_sum_tree_chip =
+            MerkleSumTreeChip::<N_CURRENCIES>::construct(config.merkle_sum_tree_config.clone());
+
+        // load lookup table for range check, shared by all the inclusions
+        self.load(&mut layouter, config.fixed_columns[4])?;
+
+        let mut leaf_hashes = vec![];
+        let mut leaves_balances = vec![];
+        let mut roots = vec![];
+
+        // Verify the inclusion of each user entry inside the merkle sum tree
+        for inclusion in &self.inclusions {
+            let (_, leaf_balances, leaf_hash) = inclusion.assign_entry(&mut layouter, &config)?;
+
+            let root = inclusion.assign_path(
+                &mut layouter,
+                &config,
+                leaf_hash.clone(),
+                leaf_balances.clone(),
+            )?;
+
+            leaf_hashes.push(leaf_hash);
+            leaves_balances.push(leaf_balances);
+            roots.push(root);
+        }
+
+        // All the inclusions must be anchored to the same root
+        for (user, (root_hash, _)) in roots.iter().enumerate().skip(1) {
+            layouter.assign_region(
+                || format!("user {}: enforce same root", user),
+                |mut region| region.constrain_equal(root_hash.cell(), roots[0].0.cell()),
+            )?;
+        }
+
+        // Sum the balances of the users for each currency
+        let mut sums = vec![];
+        for currency in 0..N_CURRENCIES {
+            let mut sum = leaves_balances[0][currency].clone();
+            for (user, leaf_balances) in leaves_balances.iter().enumerate().skip(1) {
+                sum = merkle_sum_tree_chip.sum_balances_per_level(
+                    layouter.namespace(|| format!("currency {}: add user {}", currency, user)),
+                    &sum,
+                    &leaf_balances[currency],
+                )?;
+            }
+            sums.push(sum);
+        }
+
+        let (root_hash, root_balances) = &roots[0];
+
+        let exposed_cells = leaf_hashes
+            .iter()
+            .chain(std::iter::once(root_hash))
+            .chain(root_balances.iter())
+            .chain(sums.iter());
+
+        for (row, cell) in exposed_cells.enumerate() {
+            self.expose_public(
+                layouter.namespace(|| format!("public input {}", row)),
+                cell,
+                row,
+                config.instance,
+            )?;
+        }
+
+        Ok(())
+    }
+}
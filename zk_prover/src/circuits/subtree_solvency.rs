@@ -0,0 +1,409 @@
+use crate::chips::merkle_sum_tree::MerkleSumTreeChip;
+use crate::chips::poseidon::hash::PoseidonChip;
+use crate::chips::poseidon::poseidon_spec::PoseidonSpec;
+use crate::chips::range::range_check::RangeCheckChip;
+use crate::circuits::merkle_sum_tree::MstInclusionConfig;
+use crate::circuits::traits::CircuitBase;
+use crate::circuits::WithInstances;
+use crate::merkle_sum_tree::utils::big_uint_to_fp;
+use crate::merkle_sum_tree::{Node, Tree};
+use halo2_proofs::circuit::{AssignedCell, Layouter, SimpleFloorPlanner};
+use halo2_proofs::halo2curves::bn256::Fr as Fp;
+use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error};
+use num_bigint::BigUint;
+
+/// Circuit for verifying that the balances of a subtree of the merkle sum tree are covered by a declared sum, so that the solvency audit of a large tree can be split in several proofs.
+///
+/// The circuit hashes the preimage of the subtree root, follows the path from the subtree root up to the root of the merkle sum tree and enforces that, for each currency, `declared_sum - subtree_balance` lies within the range defined by N_BYTES, namely that `subtree_balance <= declared_sum`.
+/// The path indices are exposed as public inputs, so that the verifier can check that the proofs cover distinct subtrees, and add up the declared sums off-chain.
+///
+/// # Type Parameters
+///
+/// * `LEVELS`: The number of levels from the subtree root to the root of the merkle sum tree. For example the two subtrees below the root have 1 level.
+/// * `N_CURRENCIES`: The number of currencies for which the solvency is verified.
+/// * `N_BYTES`: The number of bytes in which the balances, the sibling balances and the declared sums should lie
+///
+/// # Fields
+///
+/// * `subtree_root_hash_preimage`: The preimage of the subtree root hash, namely the subtree balances followed by the hashes of its children
+/// * `path_indices`: The boolean indices of the path elements from the subtree root to the root. The length of this vector is LEVELS
+/// * `sibling_middle_node_hash_preimages`: The preimages of the hashes of the sibling nodes along the path. The length of this vector is LEVELS
+/// * `declared_sums`: The declared sum covering the subtree balance for each currency
+/// * `root`: The root of the merkle sum tree
+#[derive(Clone)]
+pub struct SubtreeSolvencyCircuit<
+    const LEVELS: usize,
+    const N_CURRENCIES: usize,
+    const N_BYTES: usize,
+> where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    pub subtree_root_hash_preimage: [Fp; N_CURRENCIES + 2],
+    pub path_indices: Vec<Fp>,
+    pub sibling_middle_node_hash_preimages: Vec<[Fp; N_CURRENCIES + 2]>,
+    pub declared_sums: [Fp; N_CURRENCIES],
+    pub root: Node<N_CURRENCIES>,
+}
+
+impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize> WithInstances
+    for SubtreeSolvencyCircuit<LEVELS, N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    /// Returns the number of public inputs of the circuit. It is {1 + N_CURRENCIES + LEVELS}, namely the root hash, the declared sums and the path indices.
+    fn num_instances(&self) -> usize {
+        1 + N_CURRENCIES + LEVELS
+    }
+    /// Returns the values of the public inputs of the circuit. Namely the root hash of the merkle sum tree, the declared sums and the path indices of the subtree root.
+    fn instances(&self) -> Vec<Vec<Fp>> {
+        let mut instance = vec![self.root.hash];
+        instance.extend_from_slice(&self.declared_sums);
+        instance.extend_from_slice(&self.path_indices);
+        vec![instance]
+    }
+}
+
+impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize> CircuitBase
+    for SubtreeSolvencyCircuit<LEVELS, N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+}
+
+impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize>
+    SubtreeSolvencyCircuit<LEVELS, N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    pub fn init_empty() -> Self {
+        Self {
+            subtree_root_hash_preimage: [Fp::zero(); N_CURRENCIES + 2],
+            path_indices: vec![Fp::zero(); LEVELS],
+            sibling_middle_node_hash_preimages: vec![[Fp::zero(); N_CURRENCIES + 2]; LEVELS],
+            declared_sums: [Fp::zero(); N_CURRENCIES],
+            root: Node::init_empty(),
+        }
+    }
+
+    /// Initializes the circuit with the subtree whose root is the node at `index` of `level` of the tree, level 0 being the leaves, and the sums declared to cover its balances.
+    ///
+    /// Returns an error if the subtree root is a leaf or if the number of levels from the subtree root to the root doesn't match `LEVELS`.
+    pub fn init(
+        tree: &(impl Tree<N_CURRENCIES> + ?Sized),
+        level: usize,
+        index: usize,
+        declared_sums: [BigUint; N_CURRENCIES],
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let depth = *tree.depth();
+
+        if level == 0 || level > depth {
+            return Err(Box::from(
+                "The subtree root must be a middle node of the tree",
+            ));
+        }
+        if depth - level != LEVELS {
+            return Err(Box::from(
+                "The number of levels above the subtree root doesn't match the LEVELS of the circuit",
+            ));
+        }
+
+        let nodes = tree.nodes();
+        if index >= nodes[level].len() {
+            return Err(Box::from("Index out of bounds"));
+        }
+
+        let middle_node_hash_preimage = |level: usize, index: usize| {
+            let left_child = &nodes[level - 1][2 * index];
+            let right_child = &nodes[level - 1][2 * index + 1];
+
+            let mut preimage = [Fp::zero(); N_CURRENCIES + 2];
+            for (i, balance) in preimage.iter_mut().enumerate().take(N_CURRENCIES) {
+                *balance = left_child.balances[i] + right_child.balances[i];
+            }
+            preimage[N_CURRENCIES] = left_child.hash;
+            preimage[N_CURRENCIES + 1] = right_child.hash;
+            preimage
+        };
+
+        let subtree_root_hash_preimage = middle_node_hash_preimage(level, index);
+
+        let mut path_indices = Vec::with_capacity(LEVELS);
+        let mut sibling_middle_node_hash_preimages = Vec::with_capacity(LEVELS);
+        let mut current_index = index;
+
+        for current_level in level..depth {
+            let position = current_index % 2;
+            let sibling_index = current_index - position + (1 - position);
+
+            sibling_middle_node_hash_preimages
+                .push(middle_node_hash_preimage(current_level, sibling_index));
+            path_indices.push(Fp::from(position as u64));
+
+            current_index /= 2;
+        }
+
+        Ok(Self {
+            subtree_root_hash_preimage,
+            path_indices,
+            sibling_middle_node_hash_preimages,
+            declared_sums: declared_sums.map(|sum| big_uint_to_fp(&sum)),
+            root: tree.root().clone(),
+        })
+    }
+
+    /// Assigns the preimage of a middle node to the witness and hashes it.
+    ///
+    /// Returns the node hash and the assigned node balances.
+    fn assign_middle_node(
+        &self,
+        layouter: &mut impl Layouter<Fp>,
+        config: &MstInclusionConfig<N_CURRENCIES, N_BYTES>,
+        preimage: &[Fp; N_CURRENCIES + 2],
+        name: &str,
+    ) -> Result<(AssignedCell<Fp, Fp>, Vec<AssignedCell<Fp, Fp>>), Error> {
+        let poseidon_middle_chip =
+            PoseidonChip::<PoseidonSpec, 2, 1, { N_CURRENCIES + 2 }>::construct(
+                config.poseidon_middle_config.clone(),
+            );
+
+        let mut hasher_input_vec = vec![];
+
+        for (currency, balance) in preimage.iter().enumerate().take(N_CURRENCIES) {
+            let balance = self.assign_value_to_witness(
+                layouter.namespace(|| format!("{}: balance {}", name, currency)),
+                *balance,
+                "node balance",
+                config.advices[1],
+            )?;
+            hasher_input_vec.push(balance);
+        }
+
+        for (i, child_hash) in preimage.iter().enumerate().skip(N_CURRENCIES) {
+            let child_hash = self.assign_value_to_witness(
+                layouter.namespace(|| format!("{}: child hash {}", name, i - N_CURRENCIES)),
+                *child_hash,
+                "child hash",
+                config.advices[2],
+            )?;
+            hasher_input_vec.push(child_hash);
+        }
+
+        let balances = hasher_input_vec[..N_CURRENCIES].to_vec();
+
+        let hasher_input: [AssignedCell<Fp, Fp>; N_CURRENCIES + 2] =
+            match hasher_input_vec.try_into() {
+                Ok(arr) => arr,
+                Err(_) => panic!("Failed to convert Vec to Array"),
+            };
+
+        let hash = poseidon_middle_chip.hash(
+            layouter.namespace(|| format!("{}: perform poseidon hash", name)),
+            hasher_input,
+        )?;
+
+        Ok((hash, balances))
+    }
+}
+
+impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize> Circuit<Fp>
+    for SubtreeSolvencyCircuit<LEVELS, N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    type Config = MstInclusionConfig<N_CURRENCIES, N_BYTES>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::init_empty()
+    }
+
+    /// Configures the circuit. The configuration is shared with the Mst Inclusion circuit
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        MstInclusionConfig::<N_CURRENCIES, N_BYTES>::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let merkle_sum_tree_chip =
+            MerkleSumTreeChip::<N_CURRENCIES>::construct(config.merkle_sum_tree_config.clone());
+
+        let poseidon_middle_chip =
+            PoseidonChip::<PoseidonSpec, 2, 1, { N_CURRENCIES + 2 }>::construct(
+                config.poseidon_middle_config.clone(),
+            );
+
+        let range_check_chip = RangeCheckChip::<N_BYTES>::construct(config.range_check_config);
+
+        // load lookup table for range check, shared by the sibling balances and the balance deltas
+        self.load(&mut layouter, config.fixed_columns[4])?;
+
+        // Hash the preimage of the subtree root, binding the subtree balances to the subtree root hash
+        let (subtree_hash, subtree_balances) = self.assign_middle_node(
+            &mut layouter,
+            &config,
+            &self.subtree_root_hash_preimage,
+            "subtree root",
+        )?;
+
+        let mut current_hash = subtree_hash;
+        let mut current_balances = subtree_balances.clone();
+        let mut swap_bits = vec![];
+
+        for level in 0..LEVELS {
+            let namespace_prefix = format!("level {}", level);
+
+            let (sibling_hash, sibling_balances) = self.assign_middle_node(
+                &mut layouter,
+                &config,
+                &self.sibling_middle_node_hash_preimages[level],
+                &format!("{}: sibling", namespace_prefix),
+            )?;
+
+            // Each sibling balance is constrained to be within the range defined by N_BYTES
+            for (currency, sibling_balance) in sibling_balances.iter().enumerate() {
+                range_check_chip.assign(
+                    layouter.namespace(|| {
+                        format!(
+                            "{}: currency {}: range check sibling balance",
+                            namespace_prefix, currency
+                        )
+                    }),
+                    sibling_balance,
+                )?;
+            }
+
+            let swap_bit_level = self.assign_value_to_witness(
+                layouter.namespace(|| format!("{}: assign swap bit", namespace_prefix)),
+                self.path_indices[level],
+                "swap bit",
+                config.advices[0],
+            )?;
+
+            let (hash_left_current, hash_right_current) = merkle_sum_tree_chip
+                .swap_hashes_per_level(
+                    layouter.namespace(|| format!("{}: swap hashes", namespace_prefix)),
+                    &current_hash,
+                    &sibling_hash,
+                    &swap_bit_level,
+                )?;
+
+            let mut next_balances = vec![];
+
+            for currency in 0..N_CURRENCIES {
+                let next_balance = merkle_sum_tree_chip.sum_balances_per_level(
+                    layouter.namespace(|| {
+                        format!(
+                            "{}: currency {}: perform balance sum",
+                            namespace_prefix, currency
+                        )
+                    }),
+                    &current_balances[currency],
+                    &sibling_balances[currency],
+                )?;
+
+                next_balances.push(next_balance);
+            }
+
+            let middle_hasher_input_vec: Vec<AssignedCell<Fp, Fp>> = next_balances
+                .iter()
+                .chain([hash_left_current].iter())
+                .chain([hash_right_current].iter())
+                .map(|x| x.to_owned())
+                .collect();
+
+            let middle_hasher_input: [AssignedCell<Fp, Fp>; N_CURRENCIES + 2] =
+                match middle_hasher_input_vec.try_into() {
+                    Ok(arr) => arr,
+                    Err(_) => panic!("Failed to convert Vec to Array"),
+                };
+
+            current_hash = poseidon_middle_chip.hash(
+                layouter.namespace(|| format!("{}: perform poseidon hash", namespace_prefix)),
+                middle_hasher_input,
+            )?;
+            current_balances = next_balances;
+            swap_bits.push(swap_bit_level);
+        }
+
+        let mut declared_sums = vec![];
+
+        for (currency, subtree_balance) in subtree_balances.iter().enumerate() {
+            let declared_sum = self.assign_value_to_witness(
+                layouter.namespace(|| format!("currency {}: assign declared sum", currency)),
+                self.declared_sums[currency],
+                "declared sum",
+                config.advices[0],
+            )?;
+
+            // Assign the balance delta `declared_sum - subtree_balance` to the witness
+            let delta = layouter.assign_region(
+                || format!("assign balance delta {}", currency),
+                |mut region| {
+                    region.assign_advice(
+                        || "balance delta",
+                        config.advices[1],
+                        0,
+                        || declared_sum.value().copied() - subtree_balance.value().copied(),
+                    )
+                },
+            )?;
+
+            // Constrain `subtree_balance + delta = declared_sum`
+            let computed_declared_sum = merkle_sum_tree_chip.sum_balances_per_level(
+                layouter.namespace(|| format!("currency {}: sum balance delta", currency)),
+                subtree_balance,
+                &delta,
+            )?;
+
+            layouter.assign_region(
+                || format!("currency {}: enforce declared sum", currency),
+                |mut region| {
+                    region.constrain_equal(computed_declared_sum.cell(), declared_sum.cell())
+                },
+            )?;
+
+            // A subtree balance greater than the declared sum wraps around the field and fails the range check
+            range_check_chip.assign(
+                layouter.namespace(|| format!("currency {}: range check balance delta", currency)),
+                &delta,
+            )?;
+
+            declared_sums.push(declared_sum);
+        }
+
+        self.expose_public(
+            layouter.namespace(|| "public root hash"),
+            &current_hash,
+            0,
+            config.instance,
+        )?;
+
+        for (currency, declared_sum) in declared_sums.iter().enumerate() {
+            self.expose_public(
+                layouter.namespace(|| format!("public declared sum {}", currency)),
+                declared_sum,
+                1 + currency,
+                config.instance,
+            )?;
+        }
+
+        for (level, swap_bit) in swap_bits.iter().enumerate() {
+            self.expose_public(
+                layouter.namespace(|| format!("public path index {}", level)),
+                swap_bit,
+                1 + N_CURRENCIES + level,
+                config.instance,
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -2,20 +2,63 @@
 mod test {
 
     use crate::circuits::WithInstances;
+    use crate::merkle_sum_tree::utils::fp_to_big_uint;
     use crate::merkle_sum_tree::{MerkleSumTree, Tree};
     use crate::{
         circuits::{
+            balance_delta::BalanceDeltaCircuit,
             merkle_sum_tree::MstInclusionCircuit,
-            utils::{full_prover, full_verifier, generate_setup_artifacts},
+            subset_sum::SubsetSumCircuit,
+            subtree_solvency::SubtreeSolvencyCircuit,
+            utils::{
+                batch_full_verifier, calibrate_proving_time, compute_instance_commitment,
+                count_lookups, estimate_circuit_cost, estimate_proving_time,
+                estimate_proving_time_with_calibration, estimate_verify_gas, export_vk_solidity,
+                full_prover, full_prover_cancellable, full_prover_keccak, full_prover_with_domain,
+                full_verifier, full_verifier_from_vk_bytes, full_verifier_keccak,
+                full_verifier_partial, full_verifier_with_domain, full_verifier_with_shape_check,
+                generate_setup_artifacts, read_pk, read_vk, verify_inclusion_wasm, vk_shape,
+                write_pk, write_vk, Cancelled, ShapeMismatch,
+            },
+            zero_balance::ZeroBalanceCircuit,
         },
-        merkle_sum_tree::Entry,
+        merkle_sum_tree::{AnonEntry, Entry, MerkleProof},
     };
     use halo2_proofs::{
+        arithmetic::{compute_inner_product, eval_polynomial, CurveAffine},
         dev::{FailureLocation, MockProver, VerifyFailure},
-        halo2curves::bn256::Fr as Fp,
-        plonk::Any,
+        halo2curves::{
+            bn256::{Fq, Fr as Fp, G1Affine},
+            ff::{Field, PrimeField},
+            group::{Curve, GroupEncoding},
+        },
+        plonk::{keygen_pk, keygen_vk, Any},
+        poly::{
+            commitment::{Blind, Params, Prover, Verifier},
+            kzg::{
+                multiopen::{ProverSHPLONK, VerifierSHPLONK},
+                strategy::SingleStrategy,
+            },
+            ProverQuery, VerificationStrategy, VerifierQuery,
+        },
+        transcript::{
+            Blake2bRead, Blake2bWrite, Challenge255, Transcript, TranscriptReadBuffer,
+            TranscriptWriterBuffer,
+        },
+        SerdeFormat,
+    };
+    use halo2_solidity_verifier::{
+        compile_solidity, encode_calldata, BatchOpenScheme::Bdfg21, Evm, SolidityGenerator,
+    };
+    use num_bigint::{BigUint, ToBigUint};
+    use rand::rngs::OsRng;
+    use std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        time::Duration,
     };
-    use num_bigint::ToBigUint;
 
     const N_CURRENCIES: usize = 2;
     const LEVELS: usize = 4;
@@ -87,6 +130,784 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_full_prover_keccak() {
+        let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init_empty();
+        let (params, pk, vk) = generate_setup_artifacts(K, None, circuit).unwrap();
+
+        let merkle_sum_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+        let merkle_proof = merkle_sum_tree.generate_proof(0).unwrap();
+        let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init(merkle_proof);
+
+        let keccak_proof = full_prover_keccak(&params, &pk, circuit.clone(), circuit.instances());
+
+        // A proof generated with the Keccak transcript verifies with the Keccak verifier only
+        assert!(full_verifier_keccak(
+            &params,
+            &vk,
+            keccak_proof.clone(),
+            circuit.instances()
+        ));
+        assert!(!full_verifier(
+            &params,
+            &vk,
+            keccak_proof,
+            circuit.instances()
+        ));
+
+        // And conversely for a proof generated with the Blake2b transcript
+        let blake2b_proof = full_prover(&params, &pk, circuit.clone(), circuit.instances());
+        assert!(!full_verifier_keccak(
+            &params,
+            &vk,
+            blake2b_proof,
+            circuit.instances()
+        ));
+    }
+
+    #[test]
+    fn test_full_prover_with_domain() {
+        let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init_empty();
+        let (params, pk, vk) = generate_setup_artifacts(K, None, circuit).unwrap();
+
+        let merkle_sum_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+        let merkle_proof = merkle_sum_tree.generate_proof(0).unwrap();
+        let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init(merkle_proof);
+
+        let proof = full_prover_with_domain(
+            &params,
+            &pk,
+            circuit.clone(),
+            circuit.instances(),
+            "summa-solvency-v2",
+        );
+
+        assert!(full_verifier_with_domain(
+            &params,
+            &vk,
+            proof.clone(),
+            circuit.instances(),
+            "summa-solvency-v2"
+        ));
+
+        // The proof doesn't verify under a different domain tag, nor without domain tag
+        assert!(!full_verifier_with_domain(
+            &params,
+            &vk,
+            proof.clone(),
+            circuit.instances(),
+            "summa-solvency-v1"
+        ));
+        assert!(!full_verifier(&params, &vk, proof, circuit.instances()));
+    }
+
+    #[test]
+    fn test_full_verifier_partial() {
+        let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init_empty();
+        let (params, pk, vk) = generate_setup_artifacts(K, None, circuit).unwrap();
+
+        let merkle_sum_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+        let merkle_proof = merkle_sum_tree.generate_proof(0).unwrap();
+        let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init(merkle_proof);
+
+        let proof = full_prover(&params, &pk, circuit.clone(), circuit.instances());
+        let claimed_public_inputs = circuit.instances()[0].clone();
+
+        // The verifier knows the leaf hash and the root hash, the root balances are masked as unknown
+        let mut known_public_inputs = vec![
+            Some(claimed_public_inputs[0]),
+            Some(merkle_sum_tree.root().hash),
+        ];
+        known_public_inputs.extend(std::iter::repeat(None).take(N_CURRENCIES));
+
+        assert!(full_verifier_partial(
+            &params,
+            &vk,
+            proof.clone(),
+            &claimed_public_inputs,
+            &known_public_inputs
+        ));
+
+        // A known slot that differs from the claimed public inputs is rejected
+        let mut wrong_known_public_inputs = known_public_inputs.clone();
+        wrong_known_public_inputs[1] = Some(Fp::from(1u64));
+        assert!(!full_verifier_partial(
+            &params,
+            &vk,
+            proof.clone(),
+            &claimed_public_inputs,
+            &wrong_known_public_inputs
+        ));
+
+        // The proof doesn't verify with a wrong claimed root balance, even if the slot is unknown
+        let mut wrong_claimed_public_inputs = claimed_public_inputs.clone();
+        wrong_claimed_public_inputs[2] += Fp::one();
+        assert!(!full_verifier_partial(
+            &params,
+            &vk,
+            proof.clone(),
+            &wrong_claimed_public_inputs,
+            &known_public_inputs
+        ));
+
+        // The known public inputs must have one slot per public input
+        assert!(!full_verifier_partial(
+            &params,
+            &vk,
+            proof,
+            &claimed_public_inputs,
+            &known_public_inputs[..2]
+        ));
+    }
+
+    #[test]
+    fn test_batch_full_verifier() {
+        // The balance delta circuit verifies two inclusions, so it requires more rows than the Mst Inclusion circuit.
+        // All the proofs of a batch must share the same public setup, hence the Mst Inclusion circuit is keyed with the same params
+        let k = 12;
+
+        let balance_delta_circuit =
+            BalanceDeltaCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init_empty();
+        let (params, balance_delta_pk, balance_delta_vk) =
+            generate_setup_artifacts(k, None, balance_delta_circuit).unwrap();
+
+        let inclusion_circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init_empty();
+        let inclusion_vk = keygen_vk(&params, &inclusion_circuit).unwrap();
+        let inclusion_pk = keygen_pk(&params, inclusion_vk.clone(), &inclusion_circuit).unwrap();
+
+        let merkle_sum_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        // The balances of user #0 didn't change between the two snapshots
+        let balance_delta_circuit = BalanceDeltaCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init(
+            merkle_sum_tree.generate_proof(0).unwrap(),
+            merkle_sum_tree.generate_proof(0).unwrap(),
+        );
+        let balance_delta_proof = full_prover(
+            &params,
+            &balance_delta_pk,
+            balance_delta_circuit.clone(),
+            balance_delta_circuit.instances(),
+        );
+
+        let mut items = vec![(
+            &balance_delta_vk,
+            balance_delta_proof,
+            balance_delta_circuit.instances(),
+        )];
+
+        for user_index in 0..2 {
+            let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init(
+                merkle_sum_tree.generate_proof(user_index).unwrap(),
+            );
+            let proof = full_prover(&params, &inclusion_pk, circuit.clone(), circuit.instances());
+            items.push((&inclusion_vk, proof, circuit.instances()));
+        }
+
+        assert!(batch_full_verifier(&params, &items));
+
+        // Corrupting a single proof makes the whole batch fail
+        let mut corrupted_items = items.clone();
+        let proof = &mut corrupted_items[1].1;
+        let last = proof.len() - 1;
+        proof[last] ^= 1;
+        assert!(!batch_full_verifier(&params, &corrupted_items));
+
+        // As does pairing a proof with the public inputs of another proof
+        let mut wrong_inputs_items = items;
+        wrong_inputs_items[1].2 = wrong_inputs_items[2].2.clone();
+        assert!(!batch_full_verifier(&params, &wrong_inputs_items));
+    }
+
+    #[test]
+    fn test_estimate_verify_gas() {
+        let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init_empty();
+        let (params, pk, vk) = generate_setup_artifacts(K, None, circuit.clone()).unwrap();
+
+        let generator = SolidityGenerator::new(&params, &vk, Bdfg21, circuit.num_instances());
+        let deployment_code = compile_solidity(generator.render().unwrap());
+
+        let merkle_sum_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+        let merkle_proof = merkle_sum_tree.generate_proof(0).unwrap();
+        let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init(merkle_proof);
+
+        let proof = full_prover_keccak(&params, &pk, circuit.clone(), circuit.instances());
+        let gas =
+            estimate_verify_gas(deployment_code.clone(), &circuit.instances()[0], &proof).unwrap();
+
+        // The gas is the one measured by the EVM of the Solidity verifier generator for the same verification
+        let mut evm = Evm::default();
+        let verifier_address = evm.create(deployment_code.clone());
+        let (measured_gas, _) = evm.call(
+            verifier_address,
+            encode_calldata(None, &proof, &circuit.instances()[0]),
+        );
+        assert_eq!(gas, measured_gas);
+
+        // A verification that reverts is reported as an error
+        let mut invalid_instances = circuit.instances()[0].clone();
+        invalid_instances[0] += Fp::one();
+        assert_eq!(
+            estimate_verify_gas(deployment_code, &invalid_instances, &proof),
+            Err("the proof verification reverted")
+        );
+    }
+
+    #[cfg(feature = "test-cache")]
+    #[test]
+    fn test_setup_params_cache() {
+        let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init_empty();
+
+        let (params_1, _, _) = generate_setup_artifacts(K, None, circuit.clone()).unwrap();
+        let (params_2, _, _) = generate_setup_artifacts(K, None, circuit).unwrap();
+
+        assert_eq!(params_1.k(), params_2.k());
+
+        // Two independent setups would sample different toxic waste, so equal params mean the cached setup was reused
+        let mut params_1_bytes = vec![];
+        params_1.write(&mut params_1_bytes).unwrap();
+        let mut params_2_bytes = vec![];
+        params_2.write(&mut params_2_bytes).unwrap();
+        assert_eq!(params_1_bytes, params_2_bytes);
+    }
+
+    #[test]
+    fn test_full_verifier_with_shape_check() {
+        let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init_empty();
+        let (params, pk, vk) = generate_setup_artifacts(K, None, circuit).unwrap();
+
+        let shape = vk_shape(&vk);
+        assert_eq!(shape.k, K);
+        assert_eq!(shape.num_instance_columns, 1);
+        assert_eq!(shape.num_advice_columns, 3);
+
+        let merkle_sum_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+        let merkle_proof = merkle_sum_tree.generate_proof(0).unwrap();
+        let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init(merkle_proof);
+
+        let proof = full_prover(&params, &pk, circuit.clone(), circuit.instances());
+
+        assert_eq!(
+            full_verifier_with_shape_check(
+                &params,
+                &vk,
+                proof.clone(),
+                circuit.instances(),
+                circuit.num_instances()
+            ),
+            Ok(true)
+        );
+
+        // An instance vector missing the last root balance is rejected with a descriptive error
+        let mut wrong_length_instances = circuit.instances();
+        wrong_length_instances[0].pop();
+        let error = full_verifier_with_shape_check(
+            &params,
+            &vk,
+            proof.clone(),
+            wrong_length_instances,
+            circuit.num_instances(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error,
+            ShapeMismatch::Instances {
+                column: 0,
+                expected: 2 + N_CURRENCIES,
+                found: 1 + N_CURRENCIES
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Shape mismatch: instance column 0 expects {} public inputs, found {}",
+                2 + N_CURRENCIES,
+                1 + N_CURRENCIES
+            )
+        );
+
+        // As well as an extra instance column
+        let mut extra_column_instances = circuit.instances();
+        extra_column_instances.push(vec![Fp::zero()]);
+        assert_eq!(
+            full_verifier_with_shape_check(
+                &params,
+                &vk,
+                proof,
+                extra_column_instances,
+                circuit.num_instances()
+            ),
+            Err(ShapeMismatch::InstanceColumns {
+                expected: 1,
+                found: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_count_lookups() {
+        // The only lookup argument is the one of the range check chip, shared by every range checked value
+        assert_eq!(
+            count_lookups(&MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init_empty()),
+            1
+        );
+        assert_eq!(
+            count_lookups(&BalanceDeltaCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init_empty()),
+            1
+        );
+        assert_eq!(
+            count_lookups(&ZeroBalanceCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init_empty()),
+            1
+        );
+    }
+
+    #[test]
+    fn test_estimate_circuit_cost() {
+        let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init_empty();
+
+        let cost = estimate_circuit_cost(&circuit, K);
+        println!("Mst Inclusion circuit cost: {:?}", cost);
+
+        assert_eq!(cost.k, K);
+        // The Mst Inclusion circuit uses 3 advice columns, as required by the poseidon config, regardless of N_CURRENCIES
+        assert_eq!(cost.num_advice_columns, 3);
+        assert_eq!(cost.num_fixed_columns, 5);
+        assert_eq!(cost.num_instance_columns, 1);
+        // The range check chip performs a single lookup into the u8 table
+        assert_eq!(cost.num_lookups, 1);
+        assert!(cost.proof_size > 0);
+    }
+
+    #[test]
+    fn test_estimate_proving_time() {
+        let proving_time = calibrate_proving_time();
+        assert!(proving_time > Duration::ZERO);
+
+        // The calibration circuit has the size and currencies of the circuits of these tests, so the estimate of a single user is the calibrated proving time
+        let single_estimate =
+            estimate_proving_time_with_calibration(K, 1, N_CURRENCIES, proving_time);
+        assert!((single_estimate.as_secs_f64() / proving_time.as_secs_f64() - 1.0).abs() < 0.01);
+
+        // With the same calibration, twice as many users take twice as long
+        let estimate = estimate_proving_time_with_calibration(K, 1000, N_CURRENCIES, proving_time);
+        let doubled_estimate =
+            estimate_proving_time_with_calibration(K, 2000, N_CURRENCIES, proving_time);
+        let ratio = doubled_estimate.as_secs_f64() / estimate.as_secs_f64();
+        assert!((ratio - 2.0).abs() < 0.01);
+
+        // A bigger circuit, or one with more currencies, takes longer to prove
+        assert!(
+            estimate_proving_time_with_calibration(K + 1, 1000, N_CURRENCIES, proving_time)
+                > estimate
+        );
+        assert!(
+            estimate_proving_time_with_calibration(K, 1000, N_CURRENCIES + 1, proving_time)
+                > estimate
+        );
+
+        assert!(estimate_proving_time(K, 1000, N_CURRENCIES) > Duration::ZERO);
+    }
+
+    #[test]
+    fn test_init_from_proof() {
+        let merkle_sum_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+        let user_index = 5;
+
+        // A light client only receives the serialized merkle proof of the user
+        let serialized_proof =
+            serde_json::to_string(&merkle_sum_tree.generate_proof(user_index).unwrap()).unwrap();
+        let merkle_proof: MerkleProof<N_CURRENCIES> =
+            serde_json::from_str(&serialized_proof).unwrap();
+
+        let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init_from_proof(
+            &merkle_proof,
+            user_index,
+        )
+        .unwrap();
+        let tree_circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init(
+            merkle_sum_tree.generate_proof(user_index).unwrap(),
+        );
+        assert_eq!(circuit.instances(), tree_circuit.instances());
+
+        let valid_prover = MockProver::run(K, &circuit, circuit.instances()).unwrap();
+        valid_prover.assert_satisfied();
+
+        // The path of the user doesn't lead to another index
+        let result = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init_from_proof(
+            &merkle_proof,
+            user_index + 1,
+        );
+        assert!(matches!(
+            result,
+            Err("The path indices of the merkle proof don't match the index of the user")
+        ));
+    }
+
+    #[test]
+    fn test_depth_mismatch() {
+        let merkle_sum_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        // A tree of 8 entries has depth 3
+        let shallow_merkle_sum_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            merkle_sum_tree.entries()[..8].to_vec(),
+            merkle_sum_tree.cryptocurrencies().to_vec(),
+            false,
+        )
+        .unwrap();
+
+        assert!(merkle_sum_tree.assert_depth(LEVELS).is_ok());
+        assert_eq!(
+            shallow_merkle_sum_tree.assert_depth(LEVELS),
+            Err("The depth of the merkle sum tree doesn't match the expected depth")
+        );
+
+        // Initializing the circuit with a proof from the shallow tree should fail early
+        let merkle_proof = shallow_merkle_sum_tree.generate_proof(0).unwrap();
+        let result = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::try_init(merkle_proof);
+        assert!(matches!(
+            result,
+            Err("The depth of the merkle proof doesn't match the LEVELS of the circuit")
+        ));
+    }
+
+    #[test]
+    fn test_full_prover_cancellation() {
+        let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init_empty();
+
+        let (params, pk, vk) = generate_setup_artifacts(K, None, circuit).unwrap();
+
+        let merkle_sum_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        let merkle_proof = merkle_sum_tree.generate_proof(0).unwrap();
+
+        let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init(merkle_proof);
+
+        // A pre-cancelled token should return the error without proving
+        let cancellation_token = Arc::new(AtomicBool::new(true));
+        let result = full_prover_cancellable(
+            &params,
+            &pk,
+            circuit.clone(),
+            circuit.instances(),
+            Some(&cancellation_token),
+        );
+        assert_eq!(result, Err(Cancelled));
+
+        // Once the token is reset, the proof should be generated as usual
+        cancellation_token.store(false, Ordering::SeqCst);
+        let proof = full_prover_cancellable(
+            &params,
+            &pk,
+            circuit.clone(),
+            circuit.instances(),
+            Some(&cancellation_token),
+        )
+        .unwrap();
+
+        assert!(full_verifier(&params, &vk, proof, circuit.instances()));
+    }
+
+    #[test]
+    fn test_keys_serde_format() {
+        type InclusionCircuit = MstInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES>;
+
+        let circuit = InclusionCircuit::init_empty();
+        let (params, pk, vk) = generate_setup_artifacts(K, None, circuit).unwrap();
+
+        let merkle_sum_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+        let circuit = InclusionCircuit::init(merkle_sum_tree.generate_proof(0).unwrap());
+
+        for (format, name) in [
+            (SerdeFormat::RawBytes, "raw_bytes"),
+            (SerdeFormat::Processed, "processed"),
+        ] {
+            let pk_path = std::env::temp_dir().join(format!("summa_inclusion_pk_{}", name));
+            let pk_path = pk_path.to_str().unwrap();
+            let vk_path = std::env::temp_dir().join(format!("summa_inclusion_vk_{}", name));
+            let vk_path = vk_path.to_str().unwrap();
+
+            write_pk(&pk, pk_path, format).unwrap();
+            write_vk(&vk, vk_path, format).unwrap();
+
+            let loaded_pk = read_pk::<InclusionCircuit>(pk_path, format).unwrap();
+            let loaded_vk = read_vk::<InclusionCircuit>(vk_path, format).unwrap();
+
+            // A proof generated with the deserialized proving key verifies against the deserialized verifying key
+            let proof = full_prover(&params, &loaded_pk, circuit.clone(), circuit.instances());
+            assert!(full_verifier(
+                &params,
+                &loaded_vk,
+                proof,
+                circuit.instances()
+            ));
+
+            std::fs::remove_file(pk_path).unwrap();
+            std::fs::remove_file(vk_path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_export_vk_solidity() {
+        let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init_empty();
+        let (_, _, vk) = generate_setup_artifacts(K, None, circuit).unwrap();
+
+        let constants = export_vk_solidity(&vk);
+
+        let parse_uint256 = |name: String| {
+            let line = constants
+                .lines()
+                .find(|line| line.starts_with(&format!("uint256 constant {} = ", name)))
+                .unwrap_or_else(|| panic!("Constant {} not found", name));
+            let hex_str = line.split(" = 0x").nth(1).unwrap().trim_end_matches(';');
+            let mut repr: [u8; 32] = hex::decode(hex_str).unwrap().try_into().unwrap();
+            repr.reverse();
+            Fq::from_repr(repr).unwrap()
+        };
+
+        let commitments = [
+            ("FIXED_COMM", vk.fixed_commitments()),
+            ("PERMUTATION_COMM", vk.permutation().commitments()),
+        ];
+        let n_commitments: usize = commitments.iter().map(|(_, c)| c.len()).sum();
+        assert_eq!(constants.lines().count(), 2 * n_commitments);
+
+        // The emitted coordinates are those of the commitments of the verifying key, serialized identically
+        for (name, commitments) in commitments {
+            for (index, commitment) in commitments.iter().enumerate() {
+                let x = parse_uint256(format!("{}_{}_X", name, index));
+                let y = parse_uint256(format!("{}_{}_Y", name, index));
+                let point = G1Affine::from_xy(x, y).unwrap();
+                assert_eq!(point.to_bytes(), commitment.to_bytes());
+            }
+        }
+    }
+
+    #[test]
+    fn test_full_verifier_from_vk_bytes() {
+        let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init_empty();
+
+        let (params, pk, vk) = generate_setup_artifacts(K, None, circuit).unwrap();
+
+        let merkle_sum_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init(
+            merkle_sum_tree.generate_proof(0).unwrap(),
+        );
+
+        let proof = full_prover(&params, &pk, circuit.clone(), circuit.instances());
+
+        let mut vk_bytes = vec![];
+        vk.write(&mut vk_bytes, SerdeFormat::RawBytes).unwrap();
+
+        type InclusionCircuit = MstInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES>;
+
+        assert!(full_verifier_from_vk_bytes::<InclusionCircuit>(
+            &params,
+            &vk_bytes,
+            proof.clone(),
+            circuit.instances()
+        )
+        .unwrap());
+
+        // A malformed verifying key should return an error
+        assert!(full_verifier_from_vk_bytes::<InclusionCircuit>(
+            &params,
+            &vk_bytes[..vk_bytes.len() / 2],
+            proof,
+            circuit.instances()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_verify_inclusion_wasm() {
+        let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init_empty();
+
+        let (params, pk, vk) = generate_setup_artifacts(K, None, circuit).unwrap();
+
+        let merkle_sum_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        let merkle_proof = merkle_sum_tree.generate_proof(0).unwrap();
+
+        let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init(merkle_proof);
+
+        let proof = full_prover(&params, &pk, circuit.clone(), circuit.instances());
+
+        // Serialize every verification input to bytes, as a browser client would receive them
+        let mut vk_bytes = vec![];
+        vk.write(&mut vk_bytes, SerdeFormat::RawBytes).unwrap();
+
+        let mut params_bytes = vec![];
+        params.write(&mut params_bytes).unwrap();
+
+        let instances_flat: Vec<u8> = circuit.instances()[0]
+            .iter()
+            .flat_map(|instance| instance.to_repr())
+            .collect();
+
+        assert!(verify_inclusion_wasm::<LEVELS, N_CURRENCIES, N_BYTES>(
+            &proof,
+            &instances_flat,
+            &vk_bytes,
+            &params_bytes
+        ));
+
+        // A tampered instance should make the verification fail
+        let mut invalid_instances_flat = instances_flat.clone();
+        invalid_instances_flat[32] ^= 1;
+        assert!(!verify_inclusion_wasm::<LEVELS, N_CURRENCIES, N_BYTES>(
+            &proof,
+            &invalid_instances_flat,
+            &vk_bytes,
+            &params_bytes
+        ));
+
+        // Malformed inputs should return false rather than panic
+        assert!(!verify_inclusion_wasm::<LEVELS, N_CURRENCIES, N_BYTES>(
+            &proof,
+            &instances_flat[..31],
+            &vk_bytes,
+            &params_bytes
+        ));
+        assert!(!verify_inclusion_wasm::<LEVELS, N_CURRENCIES, N_BYTES>(
+            &proof,
+            &instances_flat,
+            &vk_bytes[..10],
+            &params_bytes
+        ));
+        assert!(!verify_inclusion_wasm::<LEVELS, N_CURRENCIES, N_BYTES>(
+            &proof,
+            &instances_flat,
+            &vk_bytes,
+            &[]
+        ));
+
+        // A params header claiming a huge `k` is rejected before anything is allocated
+        let mut oversized_params_bytes = params_bytes.clone();
+        oversized_params_bytes[..4].copy_from_slice(&28u32.to_le_bytes());
+        assert!(!verify_inclusion_wasm::<LEVELS, N_CURRENCIES, N_BYTES>(
+            &proof,
+            &instances_flat,
+            &vk_bytes,
+            &oversized_params_bytes
+        ));
+        oversized_params_bytes[..4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(!verify_inclusion_wasm::<LEVELS, N_CURRENCIES, N_BYTES>(
+            &proof,
+            &instances_flat,
+            &vk_bytes,
+            &oversized_params_bytes
+        ));
+
+        // So is a verifying key whose `k` doesn't match the params
+        let mut mismatching_vk_bytes = vk_bytes.clone();
+        mismatching_vk_bytes[..4].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(!verify_inclusion_wasm::<LEVELS, N_CURRENCIES, N_BYTES>(
+            &proof,
+            &instances_flat,
+            &mismatching_vk_bytes,
+            &params_bytes
+        ));
+    }
+
+    #[test]
+    fn test_compute_instance_commitment() {
+        let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init_empty();
+
+        let (params, pk, vk) = generate_setup_artifacts(K, None, circuit).unwrap();
+
+        let merkle_sum_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        let merkle_proof = merkle_sum_tree.generate_proof(0).unwrap();
+
+        let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init(merkle_proof);
+
+        let instances = circuit.instances()[0].clone();
+
+        // The instances are the ones accepted by `full_verifier` for a proof of the circuit
+        let proof = full_prover(&params, &pk, circuit.clone(), circuit.instances());
+        assert!(full_verifier(&params, &vk, proof, circuit.instances()));
+
+        let instance_commitment = compute_instance_commitment(&params, &vk, &instances).unwrap();
+
+        // `full_verifier` evaluates the instance column at the challenge x as the inner product of the instances with the Lagrange basis at x.
+        // The commitment should open at x to that same evaluation.
+        let domain = vk.get_domain();
+        let mut instance_column = domain.empty_lagrange();
+        instance_column[..instances.len()].copy_from_slice(&instances);
+        let instance_poly = domain.lagrange_to_coeff(instance_column);
+
+        let verifier_instance_eval = |x: Fp| {
+            let xn = x.pow_vartime([params.n(), 0, 0, 0]);
+            let l_i_s = domain.l_i_range(x, xn, 0..instances.len() as i32);
+            compute_inner_product(&instances, &l_i_s)
+        };
+
+        let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+        transcript.common_point(instance_commitment).unwrap();
+        let x: Fp = *transcript.squeeze_challenge_scalar::<()>();
+        assert_eq!(
+            eval_polynomial(&instance_poly, x),
+            verifier_instance_eval(x)
+        );
+        ProverSHPLONK::new(&params)
+            .create_proof(
+                OsRng,
+                &mut transcript,
+                vec![ProverQuery::new(x, &instance_poly, Blind::default())],
+            )
+            .unwrap();
+        let opening_proof = transcript.finalize();
+
+        let verify_opening = |commitment: G1Affine| {
+            let mut transcript =
+                Blake2bRead::<_, G1Affine, Challenge255<_>>::init(&opening_proof[..]);
+            transcript.common_point(instance_commitment).unwrap();
+            let x: Fp = *transcript.squeeze_challenge_scalar::<()>();
+            let verifier = VerifierSHPLONK::new(&params);
+            SingleStrategy::new(&params)
+                .process(|msm_accumulator| {
+                    verifier.verify_proof(
+                        &mut transcript,
+                        vec![VerifierQuery::new_commitment(
+                            &commitment,
+                            x,
+                            verifier_instance_eval(x),
+                        )],
+                        msm_accumulator,
+                    )
+                })
+                .is_ok()
+        };
+
+        assert!(verify_opening(instance_commitment));
+
+        // A different set of instances leads to a different commitment, which doesn't open to the evaluation of the verifier
+        let mut invalid_instances = instances.clone();
+        invalid_instances[0] += Fp::from(1);
+        let invalid_commitment =
+            compute_instance_commitment(&params, &vk, &invalid_instances).unwrap();
+        assert_ne!(invalid_commitment, instance_commitment);
+        assert!(!verify_opening(invalid_commitment));
+
+        // The params must match the circuit size
+        let mut downsized_params = params.clone();
+        downsized_params.downsize(K - 1);
+        assert!(compute_instance_commitment(&downsized_params, &vk, &instances).is_err());
+    }
+
     // Passing an invalid root hash in the instance column should fail the permutation check between the computed root hash and the instance column root hash
     #[test]
     fn test_invalid_root_hash() {
@@ -228,6 +1049,39 @@ mod test {
         );
     }
 
+    // Passing a sibling middle node preimage with doctored balances should fail, even when the total of the balances is preserved:
+    // - the sibling balances are hashed into the sibling node hash, which is hashed up to the root hash
+    // - the sibling balances are summed into the balances of the parent node, which are summed up to the root balances
+    #[test]
+    fn test_invalid_sibling_balances_as_witness() {
+        let merkle_sum_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        let user_index = 0;
+
+        let merkle_proof = merkle_sum_tree.generate_proof(user_index).unwrap();
+
+        let mut circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init(merkle_proof);
+
+        let instances = circuit.instances();
+
+        // move one unit of balance from the first currency to the second currency of the sibling node at level 1
+        circuit.sibling_middle_node_hash_preimages[0][0] -= Fp::from(1);
+        circuit.sibling_middle_node_hash_preimages[0][1] += Fp::from(1);
+
+        let invalid_prover = MockProver::run(K, &circuit, instances.clone()).unwrap();
+        assert!(invalid_prover.verify().is_err());
+
+        // inflate the balance of the sibling node at the last level
+        let mut circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init(
+            merkle_sum_tree.generate_proof(user_index).unwrap(),
+        );
+        circuit.sibling_middle_node_hash_preimages[LEVELS - 2][0] += Fp::from(1);
+
+        let invalid_prover = MockProver::run(K, &circuit, instances).unwrap();
+        assert!(invalid_prover.verify().is_err());
+    }
+
     // Passing an invalid leaf hash in the instance column should fail the permutation check between the (valid) leaf hash added as part of the witness and the instance column leaf hash
     #[test]
     fn test_invalid_leaf_hash_as_instance() {
@@ -432,6 +1286,272 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_anon_entry_inclusion() {
+        let merkle_sum_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        // Replace the usernames of the entries with random salts
+        let anon_entries: Vec<AnonEntry<N_CURRENCIES>> = merkle_sum_tree
+            .entries()
+            .iter()
+            .map(|entry| AnonEntry::new(Fp::random(OsRng), entry.balances().clone()))
+            .collect();
+
+        let anon_merkle_sum_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_anon_entries(
+            anon_entries.clone(),
+            merkle_sum_tree.cryptocurrencies().to_vec(),
+        )
+        .unwrap();
+
+        // The root balances don't depend on the leaf identities
+        assert_eq!(
+            anon_merkle_sum_tree.root().balances,
+            merkle_sum_tree.root().balances
+        );
+
+        let merkle_proof = anon_merkle_sum_tree.generate_proof(0).unwrap();
+        assert!(anon_merkle_sum_tree.verify_proof(&merkle_proof));
+
+        let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init(merkle_proof);
+
+        // The leaf commitment exposed by the circuit is the hash of the salt and the balances
+        assert_eq!(
+            circuit.instances()[0][0],
+            anon_entries[0].compute_leaf().hash
+        );
+        assert_eq!(circuit.instances()[0][1], anon_merkle_sum_tree.root().hash);
+
+        let valid_prover = MockProver::run(K, &circuit, circuit.instances()).unwrap();
+        valid_prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_nonce_entry_inclusion() {
+        let merkle_sum_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        // Blind the leaf identity of every user with a random nonce
+        let nonce_entries: Vec<Entry<N_CURRENCIES>> = merkle_sum_tree
+            .entries()
+            .iter()
+            .map(|entry| {
+                Entry::new_with_nonce(
+                    entry.username().to_string(),
+                    Fp::random(OsRng),
+                    entry.balances().clone(),
+                )
+            })
+            .collect();
+
+        let nonce_merkle_sum_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            nonce_entries.clone(),
+            merkle_sum_tree.cryptocurrencies().to_vec(),
+            false,
+        )
+        .unwrap();
+
+        let merkle_proof = nonce_merkle_sum_tree.generate_proof(0).unwrap();
+        let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init(merkle_proof);
+
+        // The leaf commitment exposed by the circuit is the hash of the blinded identity and the balances
+        assert_eq!(
+            circuit.instances()[0][0],
+            nonce_entries[0].compute_leaf().hash
+        );
+        assert_ne!(
+            circuit.instances()[0][0],
+            merkle_sum_tree.entries()[0].compute_leaf().hash
+        );
+
+        let valid_prover = MockProver::run(K, &circuit, circuit.instances()).unwrap();
+        valid_prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_balance_delta_increase() {
+        // The balance delta circuit verifies two inclusions, so it requires more rows than the Mst Inclusion circuit
+        let k = 12;
+
+        let old_merkle_sum_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        // The balances of user #0 increased in the new snapshot
+        let mut entries = old_merkle_sum_tree.entries().to_vec();
+        entries[0] = Entry::new(
+            entries[0].username().to_string(),
+            [11890.to_biguint().unwrap(), 41163.to_biguint().unwrap()],
+        );
+        let new_merkle_sum_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            entries,
+            old_merkle_sum_tree.cryptocurrencies().to_vec(),
+            false,
+        )
+        .unwrap();
+
+        let circuit = BalanceDeltaCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init(
+            old_merkle_sum_tree.generate_proof(0).unwrap(),
+            new_merkle_sum_tree.generate_proof(0).unwrap(),
+        );
+
+        assert_eq!(circuit.instances()[0].len(), circuit.num_instances());
+
+        // Both roots are exposed as public inputs
+        assert_eq!(circuit.instances()[0][1], old_merkle_sum_tree.root().hash);
+        assert_eq!(
+            circuit.instances()[0][2 + N_CURRENCIES + 1],
+            new_merkle_sum_tree.root().hash
+        );
+
+        let valid_prover = MockProver::run(k, &circuit, circuit.instances()).unwrap();
+
+        valid_prover.assert_satisfied();
+    }
+
+    // A decrease of any of the user balances should fail the range check on the balance delta
+    #[test]
+    fn test_balance_delta_decrease() {
+        let k = 12;
+
+        let old_merkle_sum_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        // The ETH balance of user #0 decreased in the new snapshot, while the USDT balance increased
+        let mut entries = old_merkle_sum_tree.entries().to_vec();
+        entries[0] = Entry::new(
+            entries[0].username().to_string(),
+            [11887.to_biguint().unwrap(), 41164.to_biguint().unwrap()],
+        );
+        let new_merkle_sum_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            entries,
+            old_merkle_sum_tree.cryptocurrencies().to_vec(),
+            false,
+        )
+        .unwrap();
+
+        let circuit = BalanceDeltaCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init(
+            old_merkle_sum_tree.generate_proof(0).unwrap(),
+            new_merkle_sum_tree.generate_proof(0).unwrap(),
+        );
+
+        let invalid_prover = MockProver::run(k, &circuit, circuit.instances()).unwrap();
+
+        assert!(invalid_prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_zero_balance() {
+        let merkle_sum_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        // User #0 withdrew all of its balances
+        let mut entries = merkle_sum_tree.entries().to_vec();
+        entries[0] = Entry::new(
+            entries[0].username().to_string(),
+            [BigUint::from(0u32), BigUint::from(0u32)],
+        );
+        let zero_balance_merkle_sum_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            entries,
+            merkle_sum_tree.cryptocurrencies().to_vec(),
+            false,
+        )
+        .unwrap();
+
+        let circuit = ZeroBalanceCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init(
+            zero_balance_merkle_sum_tree.generate_proof(0).unwrap(),
+        );
+
+        assert_eq!(circuit.instances()[0].len(), circuit.num_instances());
+        assert_eq!(
+            circuit.instances()[0][1],
+            zero_balance_merkle_sum_tree.root().hash
+        );
+
+        let valid_prover = MockProver::run(K, &circuit, circuit.instances()).unwrap();
+        valid_prover.assert_satisfied();
+
+        // The balances of user #0 are not zero in the original tree
+        let invalid_circuit = ZeroBalanceCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init(
+            merkle_sum_tree.generate_proof(0).unwrap(),
+        );
+
+        let invalid_prover =
+            MockProver::run(K, &invalid_circuit, invalid_circuit.instances()).unwrap();
+        assert!(invalid_prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_subtree_solvency() {
+        let merkle_sum_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        // The audit of the depth-4 tree is split between the two subtrees below the root, each with 8 users
+        let subtree_level = 3;
+        let mut total_declared_sums = [BigUint::from(0u32), BigUint::from(0u32)];
+
+        for index in 0..2 {
+            let subtree_root = &merkle_sum_tree.nodes()[subtree_level][index];
+            // The exchange declares a buffer of 1 on top of the subtree liabilities
+            let declared_sums = subtree_root
+                .balances
+                .map(|balance| fp_to_big_uint(balance) + 1u32);
+
+            for (total, declared_sum) in total_declared_sums.iter_mut().zip(declared_sums.iter()) {
+                *total += declared_sum;
+            }
+
+            let circuit = SubtreeSolvencyCircuit::<1, N_CURRENCIES, N_BYTES>::init(
+                &merkle_sum_tree,
+                subtree_level,
+                index,
+                declared_sums,
+            )
+            .unwrap();
+
+            assert_eq!(circuit.instances()[0].len(), circuit.num_instances());
+            assert_eq!(circuit.instances()[0][0], merkle_sum_tree.root().hash);
+            // The path index distinguishes the two subtrees
+            assert_eq!(circuit.instances()[0][3], Fp::from(index as u64));
+
+            let valid_prover = MockProver::run(K, &circuit, circuit.instances()).unwrap();
+            valid_prover.assert_satisfied();
+        }
+
+        // The declared sums of the subtrees cover the liabilities of the whole tree
+        for (total, root_balance) in total_declared_sums
+            .iter()
+            .zip(merkle_sum_tree.root().balances.iter())
+        {
+            assert!(*total >= fp_to_big_uint(*root_balance));
+        }
+
+        // A declared sum lower than the subtree balance should fail the range check on the balance delta
+        let subtree_root = &merkle_sum_tree.nodes()[subtree_level][0];
+        let mut declared_sums = subtree_root.balances.map(fp_to_big_uint);
+        declared_sums[1] -= 1u32;
+
+        let invalid_circuit = SubtreeSolvencyCircuit::<1, N_CURRENCIES, N_BYTES>::init(
+            &merkle_sum_tree,
+            subtree_level,
+            0,
+            declared_sums,
+        )
+        .unwrap();
+
+        let invalid_prover =
+            MockProver::run(K, &invalid_circuit, invalid_circuit.instances()).unwrap();
+        assert!(invalid_prover.verify().is_err());
+
+        // The number of levels above the subtree root must match LEVELS
+        assert!(SubtreeSolvencyCircuit::<2, N_CURRENCIES, N_BYTES>::init(
+            &merkle_sum_tree,
+            subtree_level,
+            0,
+            [BigUint::from(0u32), BigUint::from(0u32)],
+        )
+        .is_err());
+    }
+
     #[cfg(feature = "dev-graph")]
     #[test]
     fn print_mst_inclusion() {
@@ -457,4 +1577,81 @@ mod test {
             .render(K, &circuit, &root)
             .unwrap();
     }
+
+    #[test]
+    fn test_subset_sum() {
+        // The subset sum circuit verifies three inclusions, so it requires more rows than the Mst Inclusion circuit
+        let k = 13;
+
+        let merkle_sum_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        let user_indices = [0, 5, 11];
+
+        let merkle_proofs: Vec<_> = user_indices
+            .iter()
+            .map(|&index| merkle_sum_tree.generate_proof(index).unwrap())
+            .collect();
+
+        let declared_sums: [BigUint; N_CURRENCIES] = std::array::from_fn(|currency| {
+            user_indices
+                .iter()
+                .map(|&index| &merkle_sum_tree.get_entry(index).balances()[currency])
+                .sum()
+        });
+
+        let circuit = SubsetSumCircuit::<LEVELS, N_CURRENCIES, N_BYTES, 3>::init(
+            merkle_proofs.clone(),
+            declared_sums.clone(),
+        )
+        .unwrap();
+
+        assert_eq!(circuit.instances()[0].len(), circuit.num_instances());
+        assert_eq!(circuit.instances()[0][3], merkle_sum_tree.root().hash);
+
+        let valid_prover = MockProver::run(k, &circuit, circuit.instances()).unwrap();
+        valid_prover.assert_satisfied();
+
+        // A declared sum that doesn't match the balances of the users should fail
+        let mut invalid_declared_sums = declared_sums.clone();
+        invalid_declared_sums[1] += 1u32;
+        let invalid_circuit = SubsetSumCircuit::<LEVELS, N_CURRENCIES, N_BYTES, 3>::init(
+            merkle_proofs.clone(),
+            invalid_declared_sums,
+        )
+        .unwrap();
+
+        // The witness is generated from the user balances, so the declared sums only mismatch in the public inputs
+        let invalid_prover =
+            MockProver::run(k, &invalid_circuit, invalid_circuit.instances()).unwrap();
+        assert!(invalid_prover.verify().is_err());
+
+        // Inclusions anchored to different roots should fail
+        let other_merkle_sum_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16_modified.csv")
+                .unwrap();
+        let mut mixed_merkle_proofs = merkle_proofs;
+        mixed_merkle_proofs[2] = other_merkle_sum_tree.generate_proof(11).unwrap();
+        let mixed_circuit = SubsetSumCircuit::<LEVELS, N_CURRENCIES, N_BYTES, 3>::init(
+            mixed_merkle_proofs,
+            declared_sums.clone(),
+        )
+        .unwrap();
+        let invalid_prover = MockProver::run(k, &mixed_circuit, mixed_circuit.instances()).unwrap();
+        assert!(invalid_prover.verify().is_err());
+
+        // A number of merkle proofs other than N_USERS, or an empty subset, is rejected
+        assert_eq!(
+            SubsetSumCircuit::<LEVELS, N_CURRENCIES, N_BYTES, 2>::init(
+                merkle_proofs[..1].to_vec(),
+                declared_sums.clone(),
+            )
+            .err(),
+            Some("The number of merkle proofs doesn't match N_USERS")
+        );
+        assert_eq!(
+            SubsetSumCircuit::<LEVELS, N_CURRENCIES, N_BYTES, 0>::init(vec![], declared_sums).err(),
+            Some("The subset must contain at least one user")
+        );
+    }
 }
@@ -1,33 +1,60 @@
-use std::fs::File;
+use std::{
+    error::Error,
+    fmt::{self, Write as _},
+    fs::File,
+    io::{BufReader, BufWriter},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
 use ark_std::{end_timer, start_timer};
 use ethers::{
     abi::parse_abi,
     contract::BaseContract,
     types::{Bytes, U256},
+    utils::keccak256,
 };
 use halo2_proofs::{
+    arithmetic::CurveAffine,
+    dev::CircuitCost as Halo2CircuitCost,
     halo2curves::{
-        bn256::{Bn256, Fr as Fp, G1Affine},
+        bn256::{Bn256, Fq, Fr as Fp, G1Affine, G1},
         ff::PrimeField,
+        group::Curve,
+    },
+    plonk::{
+        create_proof, keygen_pk, keygen_vk, verify_proof, Circuit, ConstraintSystem, ProvingKey,
+        VerifyingKey,
     },
-    plonk::{create_proof, keygen_pk, keygen_vk, verify_proof, Circuit, ProvingKey, VerifyingKey},
     poly::{
-        commitment::{Params, ParamsProver},
+        commitment::{Blind, Params, ParamsProver},
         kzg::{
             commitment::{KZGCommitmentScheme, ParamsKZG},
             multiopen::{ProverSHPLONK, VerifierSHPLONK},
-            strategy::SingleStrategy,
+            strategy::{AccumulatorStrategy, SingleStrategy},
         },
+        VerificationStrategy,
     },
     transcript::{
-        Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+        Blake2bRead, Blake2bWrite, Challenge255, EncodedChallenge, Transcript,
+        TranscriptReadBuffer, TranscriptWrite, TranscriptWriterBuffer,
     },
+    SerdeFormat,
 };
 use halo2_solidity_verifier::{encode_calldata, Keccak256Transcript};
+use num_bigint::BigUint;
 use rand::{rngs::OsRng, RngCore};
+use revm::{
+    primitives::{CreateScheme, ExecutionResult, Output, TransactTo, TxEnv},
+    InMemoryDB, EVM,
+};
 
-use crate::circuits::WithInstances;
+use crate::circuits::{merkle_sum_tree::MstInclusionCircuit, WithInstances};
+use crate::merkle_sum_tree::utils::big_uint_to_fp;
+use crate::merkle_sum_tree::{Cryptocurrency, Entry, MerkleSumTree};
 
 /// Generate setup artifacts for a circuit of size `k`, where 2^k represents the number of rows in the circuit.
 ///
@@ -67,7 +94,7 @@ pub fn generate_setup_artifacts<C: Circuit<Fp>>(
         }
         None => {
             let timer = start_timer!(|| "None Creating params");
-            params = ParamsKZG::<Bn256>::setup(k, OsRng);
+            params = setup_params(k);
             end_timer!(timer);
         }
     }
@@ -78,6 +105,382 @@ pub fn generate_setup_artifacts<C: Circuit<Fp>>(
     Ok((params, pk, vk))
 }
 
+/// Writes the proving key to `path` using the given serialization `format`.
+///
+/// `SerdeFormat::RawBytes` is the format used across the repo. `SerdeFormat::Processed` produces smaller files that are slower to load, as the curve points have to be decompressed.
+pub fn write_pk(
+    pk: &ProvingKey<G1Affine>,
+    path: &str,
+    format: SerdeFormat,
+) -> Result<(), std::io::Error> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    pk.write(&mut writer, format)
+}
+
+/// Reads the proving key of the circuit `C` from `path`. The `format` must match the one used by `write_pk`.
+pub fn read_pk<C: Circuit<Fp>>(
+    path: &str,
+    format: SerdeFormat,
+) -> Result<ProvingKey<G1Affine>, std::io::Error> {
+    let mut reader = BufReader::new(File::open(path)?);
+    ProvingKey::<G1Affine>::read::<_, C>(&mut reader, format)
+}
+
+/// Writes the verifying key to `path` using the given serialization `format`. See `write_pk` for the available formats.
+pub fn write_vk(
+    vk: &VerifyingKey<G1Affine>,
+    path: &str,
+    format: SerdeFormat,
+) -> Result<(), std::io::Error> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    vk.write(&mut writer, format)
+}
+
+/// Reads the verifying key of the circuit `C` from `path`. The `format` must match the one used by `write_vk`.
+pub fn read_vk<C: Circuit<Fp>>(
+    path: &str,
+    format: SerdeFormat,
+) -> Result<VerifyingKey<G1Affine>, std::io::Error> {
+    let mut reader = BufReader::new(File::open(path)?);
+    VerifyingKey::<G1Affine>::read::<_, C>(&mut reader, format)
+}
+
+/// Exports the commitments of the fixed columns and of the permutation argument of `vk` as Solidity constants, to embed in a custom contract rather than using the full verifier generated by `SolidityGenerator`.
+///
+/// Every commitment point is emitted as two `uint256` constants for its coordinates, named `FIXED_COMM_<i>_X`, `FIXED_COMM_<i>_Y`, `PERMUTATION_COMM_<i>_X` and `PERMUTATION_COMM_<i>_Y`. The point at infinity is emitted as `(0, 0)`, as in the generated verifier.
+pub fn export_vk_solidity(vk: &VerifyingKey<G1Affine>) -> String {
+    let fq_to_uint256 = |fq: &Fq| {
+        let mut repr = fq.to_repr();
+        // Solidity literals are big endian
+        repr.reverse();
+        format!("0x{}", hex::encode(repr))
+    };
+
+    let mut constants = String::new();
+    for (name, commitments) in [
+        ("FIXED_COMM", vk.fixed_commitments()),
+        ("PERMUTATION_COMM", vk.permutation().commitments()),
+    ] {
+        for (index, commitment) in commitments.iter().enumerate() {
+            let (x, y) = Option::from(commitment.coordinates())
+                .map(|coordinates| (*coordinates.x(), *coordinates.y()))
+                .unwrap_or((Fq::zero(), Fq::zero()));
+
+            writeln!(
+                constants,
+                "uint256 constant {}_{}_X = {};",
+                name,
+                index,
+                fq_to_uint256(&x)
+            )
+            .unwrap();
+            writeln!(
+                constants,
+                "uint256 constant {}_{}_Y = {};",
+                name,
+                index,
+                fq_to_uint256(&y)
+            )
+            .unwrap();
+        }
+    }
+
+    constants
+}
+
+/// Estimated cost of a circuit for a given `k`, obtained without generating the proving key
+///
+/// * `k`: The circuit size, namely the circuit has 2^k rows
+/// * `num_advice_columns`, `num_fixed_columns`, `num_instance_columns`: The number of columns of each type. Fixed columns don't include the ones that selectors are compressed into during keygen
+/// * `num_selectors`: The number of selectors
+/// * `num_lookups`: The number of lookup arguments
+/// * `degree`: The maximum degree of the constraints
+/// * `minimum_rows`: The number of rows reserved for blinding factors and the last row, which can't be used by the circuit
+/// * `proof_size`: The estimated size of a proof in bytes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitCost {
+    pub k: u32,
+    pub num_advice_columns: usize,
+    pub num_fixed_columns: usize,
+    pub num_instance_columns: usize,
+    pub num_selectors: usize,
+    pub num_lookups: usize,
+    pub degree: usize,
+    pub minimum_rows: usize,
+    pub proof_size: usize,
+}
+
+/// Estimates the cost of `circuit` for a circuit size `k`, so that parameters such as `N_BYTES` or `k` can be tuned without running the keygen
+pub fn estimate_circuit_cost<C: Circuit<Fp>>(circuit: &C, k: u32) -> CircuitCost {
+    let mut cs = ConstraintSystem::<Fp>::default();
+    C::configure(&mut cs);
+
+    // halo2 lays out the circuit to measure the proof size
+    let cost = Halo2CircuitCost::<G1, C>::measure(k as _, circuit);
+    let proof_size: usize = cost.proof_size(cs.num_instance_columns()).into();
+
+    CircuitCost {
+        k,
+        num_advice_columns: cs.num_advice_columns(),
+        num_fixed_columns: cs.num_fixed_columns(),
+        num_instance_columns: cs.num_instance_columns(),
+        num_selectors: cs.num_selectors(),
+        num_lookups: cs.lookups().len(),
+        degree: cs.degree(),
+        minimum_rows: cs.minimum_rows(),
+        proof_size,
+    }
+}
+
+/// Size of the circuit proven by `calibrate_proving_time`
+const CALIBRATION_K: u32 = 11;
+
+/// Depth of the tree whose inclusion is proven by `calibrate_proving_time`
+const CALIBRATION_LEVELS: usize = 4;
+
+/// Number of currencies of the tree whose inclusion is proven by `calibrate_proving_time`
+const CALIBRATION_N_CURRENCIES: usize = 2;
+
+/// Number of bytes of the balances of the tree whose inclusion is proven by `calibrate_proving_time`
+const CALIBRATION_N_BYTES: usize = 8;
+
+type CalibrationCircuit =
+    MstInclusionCircuit<CALIBRATION_LEVELS, CALIBRATION_N_CURRENCIES, CALIBRATION_N_BYTES>;
+
+/// Measures the time to generate an inclusion proof with `full_prover` on the current machine, for a circuit of size `CALIBRATION_K` over a tree of `CALIBRATION_N_CURRENCIES` currencies.
+pub fn calibrate_proving_time() -> Duration {
+    let entries = (0..1 << CALIBRATION_LEVELS)
+        .map(|i| {
+            Entry::new(
+                format!("user_{}", i),
+                [(); CALIBRATION_N_CURRENCIES].map(|_| BigUint::from(i as u64)),
+            )
+        })
+        .collect();
+    let cryptocurrencies = (0..CALIBRATION_N_CURRENCIES)
+        .map(|i| Cryptocurrency {
+            name: format!("TOKEN_{}", i),
+            chain: "ETH".to_string(),
+            decimals: 0,
+        })
+        .collect();
+    let merkle_sum_tree =
+        MerkleSumTree::<CALIBRATION_N_CURRENCIES, CALIBRATION_N_BYTES>::from_entries(
+            entries,
+            cryptocurrencies,
+            false,
+        )
+        .expect("calibration tree should be valid");
+
+    let (params, pk, _) =
+        generate_setup_artifacts(CALIBRATION_K, None, CalibrationCircuit::init_empty())
+            .expect("calibration setup should not fail");
+
+    let circuit = CalibrationCircuit::init(
+        merkle_sum_tree
+            .generate_proof(0)
+            .expect("calibration proof should be generated"),
+    );
+    let instances = circuit.instances();
+
+    let start = Instant::now();
+    full_prover(&params, &pk, circuit, instances);
+    start.elapsed()
+}
+
+/// Estimates the wall-clock time to generate the inclusion proofs of `n_users` users with circuits of size `k`, by timing a proof of a small circuit on the current machine with `calibrate_proving_time`.
+pub fn estimate_proving_time(k: u32, n_users: usize, n_currencies: usize) -> Duration {
+    estimate_proving_time_with_calibration(k, n_users, n_currencies, calibrate_proving_time())
+}
+
+/// Same as `estimate_proving_time`, with the proving time measured beforehand by `calibrate_proving_time`, so that a scheduler can calibrate once and estimate many snapshots.
+///
+/// The calibrated time is scaled linearly with `n_users` and `n_currencies`, and by `k * 2^k` for the FFTs and MSMs over the 2^k rows of the circuit.
+pub fn estimate_proving_time_with_calibration(
+    k: u32,
+    n_users: usize,
+    n_currencies: usize,
+    calibrated_proving_time: Duration,
+) -> Duration {
+    let rows_ratio =
+        (k as f64 * (1u64 << k) as f64) / (CALIBRATION_K as f64 * (1u64 << CALIBRATION_K) as f64);
+    let currencies_ratio = n_currencies as f64 / CALIBRATION_N_CURRENCIES as f64;
+
+    Duration::from_secs_f64(
+        calibrated_proving_time.as_secs_f64() * n_users as f64 * rows_ratio * currencies_ratio,
+    )
+}
+
+/// Returns the number of lookup arguments of the circuit `C`, read from its constraint system.
+///
+/// Every lookup argument adds commitments to the proof, so tests can pin this number to catch lookups added by mistake.
+pub fn count_lookups<C: Circuit<Fp>>(_circuit: &C) -> usize {
+    let mut cs = ConstraintSystem::<Fp>::default();
+    C::configure(&mut cs);
+    cs.lookups().len()
+}
+
+/// Shape of the circuit a verifying key was generated for
+///
+/// * `k`: The circuit size, namely the circuit has 2^k rows
+/// * `num_advice_columns`, `num_fixed_columns`, `num_instance_columns`: The number of columns of each type, after keygen
+/// * `max_instances_per_column`: The number of rows usable by an instance column, namely the rows not reserved for blinding factors and the last row
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitShape {
+    pub k: u32,
+    pub num_advice_columns: usize,
+    pub num_fixed_columns: usize,
+    pub num_instance_columns: usize,
+    pub max_instances_per_column: usize,
+}
+
+/// Returns the shape of the circuit `vk` was generated for, read from its constraint system
+pub fn vk_shape(vk: &VerifyingKey<G1Affine>) -> CircuitShape {
+    let cs = vk.cs();
+    let k = vk.get_domain().k();
+
+    CircuitShape {
+        k,
+        num_advice_columns: cs.num_advice_columns(),
+        num_fixed_columns: cs.num_fixed_columns(),
+        num_instance_columns: cs.num_instance_columns(),
+        max_instances_per_column: (1usize << k) - cs.minimum_rows(),
+    }
+}
+
+/// Error returned by `full_verifier_with_shape_check` when the public inputs don't fit the circuit the verifying key was generated for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShapeMismatch {
+    /// The number of instance vectors differs from the number of instance columns of the circuit
+    InstanceColumns { expected: usize, found: usize },
+    /// The number of public inputs in an instance column differs from the number expected by the circuit
+    Instances {
+        column: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// An instance column has more public inputs than the usable rows of the circuit
+    TooManyInstances {
+        column: usize,
+        max: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for ShapeMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShapeMismatch::InstanceColumns { expected, found } => write!(
+                f,
+                "Shape mismatch: the verifying key expects {} instance columns, found {}",
+                expected, found
+            ),
+            ShapeMismatch::Instances {
+                column,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Shape mismatch: instance column {} expects {} public inputs, found {}",
+                column, expected, found
+            ),
+            ShapeMismatch::TooManyInstances { column, max, found } => write!(
+                f,
+                "Shape mismatch: instance column {} has room for at most {} public inputs, found {}",
+                column, max, found
+            ),
+        }
+    }
+}
+
+impl Error for ShapeMismatch {}
+
+/// Checks that `public_inputs` fit the circuit `vk` was generated for, before running the verification.
+///
+/// The verifying key doesn't record how many public inputs the circuit exposes, so `num_instances` is the number expected in every instance column, e.g. `WithInstances::num_instances` of the circuit.
+pub fn check_instances_shape(
+    vk: &VerifyingKey<G1Affine>,
+    public_inputs: &[Vec<Fp>],
+    num_instances: usize,
+) -> Result<(), ShapeMismatch> {
+    let shape = vk_shape(vk);
+
+    if public_inputs.len() != shape.num_instance_columns {
+        return Err(ShapeMismatch::InstanceColumns {
+            expected: shape.num_instance_columns,
+            found: public_inputs.len(),
+        });
+    }
+
+    for (column, instances) in public_inputs.iter().enumerate() {
+        if instances.len() > shape.max_instances_per_column {
+            return Err(ShapeMismatch::TooManyInstances {
+                column,
+                max: shape.max_instances_per_column,
+                found: instances.len(),
+            });
+        }
+        if instances.len() != num_instances {
+            return Err(ShapeMismatch::Instances {
+                column,
+                expected: num_instances,
+                found: instances.len(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Same as `full_verifier`, but returns a `ShapeMismatch` error rather than an opaque verification failure when the public inputs don't fit the circuit of `vk`
+pub fn full_verifier_with_shape_check(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    proof: Vec<u8>,
+    public_inputs: Vec<Vec<Fp>>,
+    num_instances: usize,
+) -> Result<bool, ShapeMismatch> {
+    check_instances_shape(vk, &public_inputs, num_instances)?;
+    Ok(full_verifier(params, vk, proof, public_inputs))
+}
+
+/// Performs an unsafe trusted setup of size `k`
+#[cfg(not(feature = "test-cache"))]
+fn setup_params(k: u32) -> ParamsKZG<Bn256> {
+    ParamsKZG::<Bn256>::setup(k, OsRng)
+}
+
+/// Performs an unsafe trusted setup of size `k`, once per distinct `k` for the whole process. Subsequent calls return a clone of the cached params.
+#[cfg(feature = "test-cache")]
+fn setup_params(k: u32) -> ParamsKZG<Bn256> {
+    use once_cell::sync::Lazy;
+    use std::{collections::HashMap, sync::Mutex};
+
+    static PARAMS_CACHE: Lazy<Mutex<HashMap<u32, ParamsKZG<Bn256>>>> =
+        Lazy::new(|| Mutex::new(HashMap::new()));
+
+    let mut cache = PARAMS_CACHE.lock().unwrap();
+    cache
+        .entry(k)
+        .or_insert_with(|| ParamsKZG::<Bn256>::setup(k, OsRng))
+        .clone()
+}
+
+/// Shared flag used to cancel a queued proof generation from another thread
+pub type CancellationToken = Arc<AtomicBool>;
+
+/// Error returned by `full_prover_cancellable` when the cancellation token is set before the proof creation starts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Proof generation cancelled")
+    }
+}
+
+impl Error for Cancelled {}
+
 /// Generates a proof given the public setup, the proving key, the initiated circuit and its public inputs.
 pub fn full_prover<C: Circuit<Fp>>(
     params: &ParamsKZG<Bn256>,
@@ -85,25 +488,98 @@ pub fn full_prover<C: Circuit<Fp>>(
     circuit: C,
     public_inputs: Vec<Vec<Fp>>,
 ) -> Vec<u8> {
-    let pf_time = start_timer!(|| "Creating proof");
+    full_prover_cancellable(params, pk, circuit, public_inputs, None)
+        .expect("prover should not be cancelled")
+}
 
+/// Same as `full_prover`, but returns `Err(Cancelled)` without proving if `cancellation_token` is set.
+///
+/// The token is only checked before the proof creation starts, as `create_proof` can't be interrupted. This still allows a server to abort queued proving work.
+pub fn full_prover_cancellable<C: Circuit<Fp>>(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: C,
+    public_inputs: Vec<Vec<Fp>>,
+    cancellation_token: Option<&CancellationToken>,
+) -> Result<Vec<u8>, Cancelled> {
+    if let Some(token) = cancellation_token {
+        if token.load(Ordering::SeqCst) {
+            return Err(Cancelled);
+        }
+    }
+
+    Ok(create_blake2b_proof(
+        params,
+        pk,
+        circuit,
+        public_inputs,
+        None,
+    ))
+}
+
+/// Same as `full_prover`, but the transcript absorbs the application specific `domain` tag before any other data.
+///
+/// The proof only verifies with `full_verifier_with_domain` and the same tag, which prevents reusing a proof across protocols.
+pub fn full_prover_with_domain<C: Circuit<Fp>>(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: C,
+    public_inputs: Vec<Vec<Fp>>,
+    domain: &str,
+) -> Vec<u8> {
+    create_blake2b_proof(params, pk, circuit, public_inputs, Some(domain))
+}
+
+/// Maps a domain tag to the field element absorbed by the transcript
+fn domain_separator(domain: &str) -> Fp {
+    big_uint_to_fp(&BigUint::from_bytes_be(&keccak256(domain.as_bytes())))
+}
+
+fn create_blake2b_proof<C: Circuit<Fp>>(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: C,
+    public_inputs: Vec<Vec<Fp>>,
+    domain: Option<&str>,
+) -> Vec<u8> {
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    if let Some(domain) = domain {
+        transcript
+            .common_scalar(domain_separator(domain))
+            .expect("writing to the transcript should not fail");
+    }
+    create_proof_with_transcript(params, pk, circuit, public_inputs, &mut transcript);
+    transcript.finalize()
+}
+
+/// Writes the proof of the circuit to `transcript`, shared by the provers over the Blake2b and Keccak transcripts.
+fn create_proof_with_transcript<
+    C: Circuit<Fp>,
+    E: EncodedChallenge<G1Affine>,
+    T: TranscriptWrite<G1Affine, E>,
+>(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: C,
+    public_inputs: Vec<Vec<Fp>>,
+    transcript: &mut T,
+) {
     let instance: Vec<&[Fp]> = public_inputs.iter().map(|input| &input[..]).collect();
     let instances = &[&instance[..]];
 
-    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
-    let result = create_proof::<
-        KZGCommitmentScheme<Bn256>,
-        ProverSHPLONK<'_, Bn256>,
-        Challenge255<G1Affine>,
-        _,
-        Blake2bWrite<Vec<u8>, G1Affine, Challenge255<G1Affine>>,
-        _,
-    >(params, pk, &[circuit], instances, OsRng, &mut transcript)
+    let pf_time = start_timer!(|| "Creating proof");
+
+    let result = create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<'_, Bn256>, E, _, T, _>(
+        params,
+        pk,
+        &[circuit],
+        instances,
+        OsRng,
+        transcript,
+    )
     .expect("prover should not fail");
     assert!(result.0.is_ok());
-    let proof = transcript.finalize();
     end_timer!(pf_time);
-    proof
 }
 
 /// Verifies a proof given the public setup, the verification key, the proof and the public inputs of the circuit.
@@ -112,22 +588,278 @@ pub fn full_verifier(
     vk: &VerifyingKey<G1Affine>,
     proof: Vec<u8>,
     public_inputs: Vec<Vec<Fp>>,
+) -> bool {
+    verify_blake2b_proof(params, vk, proof, public_inputs, None)
+}
+
+/// Verifies a proof generated by `full_prover_with_domain`. Verification fails if the proof was generated with a different `domain` tag.
+pub fn full_verifier_with_domain(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    proof: Vec<u8>,
+    public_inputs: Vec<Vec<Fp>>,
+    domain: &str,
+) -> bool {
+    verify_blake2b_proof(params, vk, proof, public_inputs, Some(domain))
+}
+
+/// Verifies a proof when the verifier only knows a subset of the public inputs, e.g. the root hash but not the root balances.
+///
+/// The instances of a halo2 proof are not committed in the proof, so the unknown slots can't be read from it: their values are taken from `claimed_public_inputs`, the public inputs shipped by the prover along with the proof. `known_public_inputs` holds one slot per public input, `Some` with the value the verifier expects or `None` if unknown.
+///
+/// On success, the following is guaranteed:
+/// * the proof is valid for `claimed_public_inputs`
+/// * every known slot is equal to the value expected by the verifier
+///
+/// Nothing more is guaranteed for the unknown slots than for any public input of a valid proof: their claimed values are the ones the circuit was proven with, and the verifier only learns them from the prover.
+///
+/// Returns false if the two slices have different lengths.
+pub fn full_verifier_partial(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    proof: Vec<u8>,
+    claimed_public_inputs: &[Fp],
+    known_public_inputs: &[Option<Fp>],
+) -> bool {
+    if claimed_public_inputs.len() != known_public_inputs.len() {
+        return false;
+    }
+
+    for (claimed, known) in claimed_public_inputs.iter().zip(known_public_inputs) {
+        if let Some(known) = known {
+            if claimed != known {
+                return false;
+            }
+        }
+    }
+
+    full_verifier(params, vk, proof, vec![claimed_public_inputs.to_vec()])
+}
+
+/// Verifies a batch of proofs, each given with its verification key and public inputs, all generated with `full_prover` under the same public setup.
+///
+/// The pairing checks of all proofs are accumulated and performed once at the end, which is cheaper than calling `full_verifier` on every proof. Returns false if any proof of the batch is invalid.
+pub fn batch_full_verifier(
+    params: &ParamsKZG<Bn256>,
+    items: &[(&VerifyingKey<G1Affine>, Vec<u8>, Vec<Vec<Fp>>)],
+) -> bool {
+    let verifier_params = params.verifier_params();
+    let mut strategy = AccumulatorStrategy::new(params);
+
+    let vf_time = start_timer!(|| format!("Verifying batch of {} proofs", items.len()));
+    for (vk, proof, public_inputs) in items {
+        let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+
+        let instance: Vec<&[Fp]> = public_inputs.iter().map(|input| &input[..]).collect();
+        let instances = &[&instance[..]];
+
+        strategy = match verify_proof::<
+            KZGCommitmentScheme<Bn256>,
+            VerifierSHPLONK<'_, Bn256>,
+            Challenge255<G1Affine>,
+            Blake2bRead<&[u8], G1Affine, Challenge255<G1Affine>>,
+            AccumulatorStrategy<'_, Bn256>,
+        >(verifier_params, vk, strategy, instances, &mut transcript)
+        {
+            Ok(strategy) => strategy,
+            Err(_) => {
+                end_timer!(vf_time);
+                return false;
+            }
+        };
+    }
+
+    let finalize_time = start_timer!(|| "Finalizing batch verification");
+    let result = strategy.finalize();
+    end_timer!(finalize_time);
+    end_timer!(vf_time);
+    result
+}
+
+fn verify_blake2b_proof(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    proof: Vec<u8>,
+    public_inputs: Vec<Vec<Fp>>,
+    domain: Option<&str>,
 ) -> bool {
     let verifier_params = params.verifier_params();
     let strategy = SingleStrategy::new(params);
     let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+    if let Some(domain) = domain {
+        if transcript.common_scalar(domain_separator(domain)).is_err() {
+            return false;
+        }
+    }
 
     let instance: Vec<&[Fp]> = public_inputs.iter().map(|input| &input[..]).collect();
     let instances = &[&instance[..]];
 
-    verify_proof::<
+    let vf_time = start_timer!(|| "Verifying proof");
+    let result = verify_proof::<
         KZGCommitmentScheme<Bn256>,
         VerifierSHPLONK<'_, Bn256>,
         Challenge255<G1Affine>,
         Blake2bRead<&[u8], G1Affine, Challenge255<G1Affine>>,
         SingleStrategy<'_, Bn256>,
     >(verifier_params, vk, strategy, instances, &mut transcript)
-    .is_ok()
+    .is_ok();
+    end_timer!(vf_time);
+    result
+}
+
+/// Same as `full_prover`, but uses a Keccak256 transcript for the Fiat-Shamir challenges, as expected by the Solidity verifier.
+pub fn full_prover_keccak<C: Circuit<Fp>>(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: C,
+    public_inputs: Vec<Vec<Fp>>,
+) -> Vec<u8> {
+    let mut transcript = Keccak256Transcript::new(Vec::new());
+    create_proof_with_transcript(params, pk, circuit, public_inputs, &mut transcript);
+    transcript.finalize()
+}
+
+/// Same as `full_verifier`, but for proofs generated by `full_prover_keccak`.
+pub fn full_verifier_keccak(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    proof: Vec<u8>,
+    public_inputs: Vec<Vec<Fp>>,
+) -> bool {
+    let verifier_params = params.verifier_params();
+    let strategy = SingleStrategy::new(params);
+    let mut transcript = Keccak256Transcript::new(&proof[..]);
+
+    let instance: Vec<&[Fp]> = public_inputs.iter().map(|input| &input[..]).collect();
+    let instances = &[&instance[..]];
+
+    let vf_time = start_timer!(|| "Verifying proof with Keccak256 transcript");
+    let result = verify_proof::<
+        KZGCommitmentScheme<Bn256>,
+        VerifierSHPLONK<'_, Bn256>,
+        _,
+        _,
+        SingleStrategy<'_, Bn256>,
+    >(verifier_params, vk, strategy, instances, &mut transcript)
+    .is_ok();
+    end_timer!(vf_time);
+    result
+}
+
+/// Verifies a proof against a verifying key serialized with `SerdeFormat::RawBytes`, e.g. the one committed on chain by the exchange, rather than a local copy.
+///
+/// Returns an error if the verifying key can't be deserialized for the circuit `C` or doesn't match the size of the public setup.
+pub fn full_verifier_from_vk_bytes<C: Circuit<Fp>>(
+    params: &ParamsKZG<Bn256>,
+    vk_bytes: &[u8],
+    proof: Vec<u8>,
+    public_inputs: Vec<Vec<Fp>>,
+) -> Result<bool, std::io::Error> {
+    let vk = VerifyingKey::<G1Affine>::read::<_, C>(&mut &vk_bytes[..], SerdeFormat::RawBytes)?;
+
+    if params.k() != vk.get_domain().k() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "verifying key doesn't match the size of the params",
+        ));
+    }
+
+    Ok(full_verifier(params, &vk, proof, public_inputs))
+}
+
+/// Computes off-chain the commitment to the instance column of a circuit, namely the linear combination of the Lagrange bases of the public setup with the public inputs of the circuit.
+///
+/// The public inputs are placed in the first rows of the instance column and the remaining rows are zero. Useful to debug instance mismatches between the prover and a verifier.
+pub fn compute_instance_commitment(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    instances: &[Fp],
+) -> Result<G1Affine, &'static str> {
+    let domain = vk.get_domain();
+
+    // The params must match the size of the circuit the verifying key was generated for
+    if params.k() != domain.k() {
+        return Err("params k doesn't match the verifying key k");
+    }
+
+    let mut instance_column = domain.empty_lagrange();
+    if instances.len() > instance_column.len() {
+        return Err("too many instances for the circuit size");
+    }
+    instance_column[..instances.len()].copy_from_slice(instances);
+
+    Ok(params
+        .commit_lagrange(&instance_column, Blind::default())
+        .to_affine())
+}
+
+/// Verifies an inclusion proof given all the verification inputs as raw bytes, so that it can be called from environments that don't have access to the halo2 types (e.g. `wasm32-unknown-unknown` bindings).
+///
+/// * `proof`: The proof bytes as returned by `full_prover`
+/// * `instances_flat`: The public inputs of the circuit, concatenated as 32-byte little-endian field element representations
+/// * `vk_bytes`: The verifying key serialized with `SerdeFormat::RawBytes`
+/// * `params_bytes`: The public setup serialized with `ParamsKZG::write`
+///
+/// Returns false if any of the inputs can't be deserialized or if the proof is invalid. It never panics on malformed input: the `k` found in the headers of `params_bytes` and `vk_bytes` is checked against the length of `params_bytes` before anything is deserialized, so that a crafted header can't make the verifier allocate more than the size of its input.
+pub fn verify_inclusion_wasm<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize>(
+    proof: &[u8],
+    instances_flat: &[u8],
+    vk_bytes: &[u8],
+    params_bytes: &[u8],
+) -> bool
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    // Serialized sizes of the points of `ParamsKZG` in the raw format used by `ParamsKZG::write`
+    const G1_RAW_BYTES: u64 = 64;
+    const G2_RAW_BYTES: u64 = 128;
+
+    // `ParamsKZG::read` sizes the params from the little-endian `k` of the header. The params hold 2^k monomial and 2^k lagrange G1 points followed by 2 G2 points
+    let k = match params_bytes.get(..4) {
+        Some(header) => u32::from_le_bytes([header[0], header[1], header[2], header[3]]),
+        None => return false,
+    };
+    if k > Fp::S || params_bytes.len() as u64 != 4 + (2u64 << k) * G1_RAW_BYTES + 2 * G2_RAW_BYTES {
+        return false;
+    }
+
+    // The verifying key starts with the big-endian `k` of its domain, which must match the params before the domain is built from it
+    match vk_bytes.get(..4) {
+        Some(header) if u32::from_be_bytes([header[0], header[1], header[2], header[3]]) == k => {}
+        _ => return false,
+    }
+
+    let params = match ParamsKZG::<Bn256>::read(&mut &params_bytes[..]) {
+        Ok(params) => params,
+        Err(_) => return false,
+    };
+
+    let vk = match VerifyingKey::<G1Affine>::read::<
+        _,
+        MstInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES>,
+    >(&mut &vk_bytes[..], SerdeFormat::RawBytes)
+    {
+        Ok(vk) => vk,
+        Err(_) => return false,
+    };
+
+    if instances_flat.len() % 32 != 0 {
+        return false;
+    }
+
+    let mut instances = Vec::with_capacity(instances_flat.len() / 32);
+    for chunk in instances_flat.chunks(32) {
+        let mut repr = [0u8; 32];
+        repr.copy_from_slice(chunk);
+        match Option::<Fp>::from(Fp::from_repr(repr)) {
+            Some(instance) => instances.push(instance),
+            None => return false,
+        }
+    }
+
+    full_verifier(&params, &vk, proof.to_vec(), vec![instances])
 }
 
 /// Generate the proof Solidity calldata for a circuit
@@ -159,6 +891,53 @@ pub fn gen_proof_solidity_calldata<C: Circuit<Fp> + WithInstances>(
     (decoded.0, decoded.1)
 }
 
+/// Estimates the gas spent by the Solidity verifier to verify `proof` against `instances`, by deploying `deployment_code` and running the verification in a local EVM.
+///
+/// The proof must be generated with the Keccak256 transcript, e.g. by `gen_proof_solidity_calldata` or `full_prover_keccak`. Returns an error if the verifier can't be deployed or if the verification reverts, e.g. for an invalid proof or instances that don't match it.
+pub fn estimate_verify_gas(
+    deployment_code: Vec<u8>,
+    instances: &[Fp],
+    proof: &[u8],
+) -> Result<u64, &'static str> {
+    let calldata = encode_calldata(None, proof, instances);
+
+    let mut evm = EVM::new();
+    evm.database(InMemoryDB::default());
+
+    let deployment = TxEnv {
+        gas_limit: u64::MAX,
+        transact_to: TransactTo::Create(CreateScheme::Create),
+        data: deployment_code.into(),
+        ..Default::default()
+    };
+    let verifier_address = match transact(&mut evm, deployment) {
+        Some((_, Output::Create(_, Some(address)))) => address,
+        _ => return Err("the verifier deployment reverted"),
+    };
+
+    let verification = TxEnv {
+        gas_limit: u64::MAX,
+        transact_to: TransactTo::Call(verifier_address),
+        data: calldata.into(),
+        ..Default::default()
+    };
+    let (gas_cost, _) =
+        transact(&mut evm, verification).ok_or("the proof verification reverted")?;
+
+    Ok(gas_cost)
+}
+
+/// Runs `tx` in the local EVM and returns the gas used and the output of the transaction, or `None` if the transaction is invalid, reverts or halts.
+fn transact(evm: &mut EVM<InMemoryDB>, tx: TxEnv) -> Option<(u64, Output)> {
+    evm.env.tx = tx;
+    match evm.transact_commit().ok()? {
+        ExecutionResult::Success {
+            gas_used, output, ..
+        } => Some((gas_used, output)),
+        _ => None,
+    }
+}
+
 fn create_proof_checked(
     params: &ParamsKZG<Bn256>,
     pk: &ProvingKey<G1Affine>,
@@ -0,0 +1,136 @@
+use crate::circuits::merkle_sum_tree::{MstInclusionCircuit, MstInclusionConfig};
+use crate::circuits::traits::CircuitBase;
+use crate::circuits::WithInstances;
+use crate::merkle_sum_tree::utils::big_uint_to_fp;
+use crate::merkle_sum_tree::MerkleProof;
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner};
+use halo2_proofs::halo2curves::bn256::Fr as Fp;
+use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error};
+
+/// Circuit for verifying that a user is included in a merkle sum tree with a zero balance in every currency, e.g. after a full withdrawal.
+///
+/// The circuit verifies the inclusion of the user entry and constrains all of its balances to be zero. Neither the leaf hash nor the root balances are exposed, so the proof reveals nothing beyond the username and the root hash of the tree. The username binds the proof to the user, otherwise it would only prove that some zero balance entry exists in the tree.
+///
+/// # Type Parameters
+///
+/// * `LEVELS`: The number of levels of the merkle sum tree.
+/// * `N_CURRENCIES`: The number of currencies for which the solvency is verified.
+/// * `N_BYTES`: The number of bytes in which the balances should lie
+///
+/// # Fields
+///
+/// * `inclusion`: The inclusion circuit of the user entry inside the merkle sum tree
+#[derive(Clone)]
+pub struct ZeroBalanceCircuit<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    pub inclusion: MstInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES>,
+}
+
+impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize> WithInstances
+    for ZeroBalanceCircuit<LEVELS, N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    /// Returns the number of public inputs of the circuit. It is 2, namely the username and the root hash of the merkle sum tree.
+    fn num_instances(&self) -> usize {
+        2
+    }
+    /// Returns the values of the public inputs of the circuit. Namely the username of the user as a field element and the root hash of the merkle sum tree.
+    fn instances(&self) -> Vec<Vec<Fp>> {
+        vec![vec![
+            big_uint_to_fp(&self.inclusion.entry.leaf_identity()),
+            self.inclusion.root.hash,
+        ]]
+    }
+}
+
+impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize> CircuitBase
+    for ZeroBalanceCircuit<LEVELS, N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+}
+
+impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize>
+    ZeroBalanceCircuit<LEVELS, N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    pub fn init_empty() -> Self {
+        Self {
+            inclusion: MstInclusionCircuit::init_empty(),
+        }
+    }
+
+    /// Initializes the circuit with the merkle proof of the user entry. The circuit is only satisfied if all the balances of the entry are zero.
+    pub fn init(merkle_proof: MerkleProof<N_CURRENCIES>) -> Self {
+        Self {
+            inclusion: MstInclusionCircuit::init(merkle_proof),
+        }
+    }
+}
+
+impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize> Circuit<Fp>
+    for ZeroBalanceCircuit<LEVELS, N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    type Config = MstInclusionConfig<N_CURRENCIES, N_BYTES>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::init_empty()
+    }
+
+    /// Configures the circuit. The configuration is shared with the Mst Inclusion circuit
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        MstInclusionConfig::<N_CURRENCIES, N_BYTES>::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let (username, leaf_balances, leaf_hash) =
+            self.inclusion.assign_entry(&mut layouter, &config)?;
+
+        // Every balance of the user entry must be zero
+        for (i, balance) in leaf_balances.iter().enumerate() {
+            layouter.assign_region(
+                || format!("enforce zero balance {}", i),
+                |mut region| region.constrain_constant(balance.cell(), Fp::zero()),
+            )?;
+        }
+
+        // load lookup table for range check
+        self.load(&mut layouter, config.fixed_columns[4])?;
+
+        let (root_hash, _) =
+            self.inclusion
+                .assign_path(&mut layouter, &config, leaf_hash, leaf_balances)?;
+
+        self.expose_public(
+            layouter.namespace(|| "public username"),
+            &username,
+            0,
+            config.instance,
+        )?;
+
+        self.expose_public(
+            layouter.namespace(|| "public root hash"),
+            &root_hash,
+            1,
+            config.instance,
+        )?;
+
+        Ok(())
+    }
+}
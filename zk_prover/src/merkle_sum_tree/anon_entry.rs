@@ -0,0 +1,42 @@
+use crate::merkle_sum_tree::utils::fp_to_big_uint;
+use crate::merkle_sum_tree::{Entry, Node};
+use halo2_proofs::halo2curves::bn256::Fr as Fp;
+use num_bigint::BigUint;
+
+/// An anonymous entry in the Merkle Sum Tree from the database of the CEX.
+/// It contains a random salt, which takes the place of the username as the leaf identity, and the balances of the user.
+#[derive(Clone, Debug, std::cmp::PartialEq)]
+pub struct AnonEntry<const N_CURRENCIES: usize> {
+    salt: Fp,
+    balances: [BigUint; N_CURRENCIES],
+}
+
+impl<const N_CURRENCIES: usize> AnonEntry<N_CURRENCIES> {
+    pub fn new(salt: Fp, balances: [BigUint; N_CURRENCIES]) -> Self {
+        AnonEntry { salt, balances }
+    }
+
+    /// The leaf node hash is equal to `H(salt, balance[0], balance[1], ... balance[N_CURRENCIES - 1])`
+    pub fn compute_leaf(&self) -> Node<N_CURRENCIES>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+    {
+        Node::leaf(&fp_to_big_uint(self.salt), &self.balances)
+    }
+
+    pub fn balances(&self) -> &[BigUint; N_CURRENCIES] {
+        &self.balances
+    }
+
+    pub fn salt(&self) -> &Fp {
+        &self.salt
+    }
+}
+
+/// The leaf hash preimage of an anonymous entry has the same shape as the one of a regular entry, with the salt in place of the hashed username.
+/// This lets anonymous entries be stored in a `MerkleSumTree` and proven by the `MstInclusionCircuit`.
+impl<const N_CURRENCIES: usize> From<AnonEntry<N_CURRENCIES>> for Entry<N_CURRENCIES> {
+    fn from(anon_entry: AnonEntry<N_CURRENCIES>) -> Self {
+        Entry::from_salt(anon_entry.salt, anon_entry.balances)
+    }
+}
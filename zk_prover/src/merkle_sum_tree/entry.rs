@@ -1,14 +1,19 @@
+use crate::chips::poseidon::poseidon_spec::PoseidonSpec;
+use crate::merkle_sum_tree::utils::{big_uint_to_fp, fp_to_big_uint};
 use crate::merkle_sum_tree::Node;
 use ethers::utils::keccak256;
+use halo2_gadgets::poseidon::primitives::{self as poseidon, ConstantLength};
+use halo2_proofs::halo2curves::bn256::Fr as Fp;
 use num_bigint::BigUint;
 
 /// An entry in the Merkle Sum Tree from the database of the CEX.
-/// It contains the username and the balances of the user.
+/// It contains the username and the balances of the user, and optionally the nonce blinding the leaf identity of the user.
 #[derive(Clone, Debug, std::cmp::PartialEq)]
 pub struct Entry<const N_CURRENCIES: usize> {
     hashed_username: BigUint,
     balances: [BigUint; N_CURRENCIES],
     username: String,
+    nonce: Option<Fp>,
 }
 
 impl<const N_CURRENCIES: usize> Entry<N_CURRENCIES> {
@@ -23,6 +28,38 @@ impl<const N_CURRENCIES: usize> Entry<N_CURRENCIES> {
             hashed_username,
             balances,
             username,
+            nonce: None,
+        }
+    }
+
+    /// Builds an entry whose leaf identity is blinded with a nonce private to the user, namely `H(hashed_username, nonce)` in place of the hashed username.
+    ///
+    /// The leaf hash is then `H(H(hashed_username, nonce), balance[0], ..., balance[N_CURRENCIES - 1])`, so that an adversary knowing the username can't confirm guessed balances against a leaf without the nonce.
+    /// The leaf hash preimage keeps the shape of the one of a regular entry, so such entries are proven by the `MstInclusionCircuit` as is, and the merkle proof of a sibling only reveals its blinded identity, not its nonce.
+    ///
+    /// Only the leaf is blinded: `username_as_big_uint` still returns the plain hashed username, so that the same user is recognized across trees whose nonces differ.
+    pub fn new_with_nonce(username: String, nonce: Fp, balances: [BigUint; N_CURRENCIES]) -> Self {
+        let mut entry = Self::new(username, balances);
+        entry.nonce = Some(nonce);
+        entry
+    }
+
+    /// Builds an entry whose leaf identity is a salt rather than a hashed username. The username is set to the hex representation of the salt.
+    pub(crate) fn from_salt(salt: Fp, balances: [BigUint; N_CURRENCIES]) -> Self {
+        Self::from_hashed_username(format!("{:?}", salt), fp_to_big_uint(salt), balances)
+    }
+
+    /// Builds an entry from an already hashed username, e.g. when deserializing a proof
+    pub(crate) fn from_hashed_username(
+        username: String,
+        hashed_username: BigUint,
+        balances: [BigUint; N_CURRENCIES],
+    ) -> Self {
+        Entry {
+            hashed_username,
+            balances,
+            username,
+            nonce: None,
         }
     }
 
@@ -34,6 +71,7 @@ impl<const N_CURRENCIES: usize> Entry<N_CURRENCIES> {
             hashed_username: BigUint::from(0u32),
             balances: empty_balances,
             username: "0".to_string(),
+            nonce: None,
         }
     }
 
@@ -41,7 +79,7 @@ impl<const N_CURRENCIES: usize> Entry<N_CURRENCIES> {
     where
         [usize; N_CURRENCIES + 1]: Sized,
     {
-        Node::leaf(&self.hashed_username, &self.balances)
+        Node::leaf(&self.leaf_identity(), &self.balances)
     }
 
     /// Stores the new balance values
@@ -55,7 +93,7 @@ impl<const N_CURRENCIES: usize> Entry<N_CURRENCIES> {
         [usize; N_CURRENCIES + 1]: Sized,
     {
         self.balances = updated_balances.clone();
-        Node::leaf(&self.hashed_username, updated_balances)
+        Node::leaf(&self.leaf_identity(), updated_balances)
     }
 
     pub fn balances(&self) -> &[BigUint; N_CURRENCIES] {
@@ -66,7 +104,23 @@ impl<const N_CURRENCIES: usize> Entry<N_CURRENCIES> {
         &self.hashed_username
     }
 
+    /// Returns the identity hashed along with the balances into the leaf, namely `H(hashed_username, nonce)` if the entry was built with `new_with_nonce` and the hashed username otherwise
+    pub fn leaf_identity(&self) -> BigUint {
+        match self.nonce {
+            Some(nonce) => fp_to_big_uint(
+                poseidon::Hash::<Fp, PoseidonSpec, ConstantLength<2>, 2, 1>::init()
+                    .hash([big_uint_to_fp(&self.hashed_username), nonce]),
+            ),
+            None => self.hashed_username.clone(),
+        }
+    }
+
     pub fn username(&self) -> &str {
         &self.username
     }
+
+    /// Returns the nonce blinding the leaf identity, if the entry was built with `new_with_nonce`
+    pub fn nonce(&self) -> Option<&Fp> {
+        self.nonce.as_ref()
+    }
 }
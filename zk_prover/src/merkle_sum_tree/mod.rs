@@ -1,8 +1,11 @@
+mod anon_entry;
 mod entry;
 mod mst;
 mod node;
+mod proof_serde;
 mod tests;
 mod tree;
+mod tree_file;
 pub mod utils;
 use halo2_proofs::halo2curves::bn256::Fr as Fp;
 
@@ -26,8 +29,23 @@ where
     pub path_indices: Vec<Fp>,
 }
 
+impl<const N_CURRENCIES: usize> MerkleProof<N_CURRENCIES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    /// Returns true if both proofs follow the same path, namely if they have the same sibling hash preimages and path indices. The entry and the root are not compared.
+    pub fn path_eq(&self, other: &Self) -> bool {
+        self.sibling_leaf_node_hash_preimage == other.sibling_leaf_node_hash_preimage
+            && self.sibling_middle_node_hash_preimages == other.sibling_middle_node_hash_preimages
+            && self.path_indices == other.path_indices
+    }
+}
+
+pub use anon_entry::AnonEntry;
 pub use entry::Entry;
 pub use mst::Cryptocurrency;
+pub use mst::EmptyEntries;
 pub use mst::MerkleSumTree;
 pub use node::Node;
 pub use tree::Tree;
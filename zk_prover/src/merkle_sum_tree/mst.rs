@@ -1,9 +1,25 @@
 use crate::merkle_sum_tree::utils::{
-    build_leaves_from_entries, build_merkle_tree_from_leaves, parse_csv_to_entries,
+    build_leaves_from_entries, build_merkle_tree_from_leaves, fp_to_big_uint, parse_csv_to_entries,
 };
-use crate::merkle_sum_tree::{Entry, Node, Tree};
+use crate::merkle_sum_tree::{AnonEntry, Entry, Node, Tree};
 use halo2_proofs::halo2curves::bn256::Fr as Fp;
 use num_bigint::BigUint;
+use std::{error::Error, fmt};
+
+/// Error returned when building a Merkle Sum Tree from an empty list of entries
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmptyEntries;
+
+impl fmt::Display for EmptyEntries {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Cannot build a merkle sum tree from an empty list of entries"
+        )
+    }
+}
+
+impl Error for EmptyEntries {}
 
 /// Merkle Sum Tree Data Structure.
 ///
@@ -51,10 +67,14 @@ impl<const N_CURRENCIES: usize, const N_BYTES: usize> Tree<N_CURRENCIES>
     }
 }
 
-#[derive(Debug, Clone)]
+/// A cryptocurrency whose balances are in the tree.
+///
+/// The balances are integers expressed in the base unit of the cryptocurrency, `decimals` is the number of decimal places of the base unit (e.g. 18 for ETH, 6 for USDT).
+#[derive(Debug, Clone, PartialEq)]
 pub struct Cryptocurrency {
     pub name: String,
     pub chain: String,
+    pub decimals: u8,
 }
 
 impl<const N_CURRENCIES: usize, const N_BYTES: usize> MerkleSumTree<N_CURRENCIES, N_BYTES> {
@@ -66,6 +86,13 @@ impl<const N_CURRENCIES: usize, const N_BYTES: usize> MerkleSumTree<N_CURRENCIES
     pub fn entries(&self) -> &[Entry<N_CURRENCIES>] {
         &self.entries
     }
+    /// Returns an error if the depth of the tree doesn't match `expected`, e.g. the `LEVELS` of the circuit that will prove inclusion in the tree
+    pub fn assert_depth(&self, expected: usize) -> Result<(), &'static str> {
+        if self.depth != expected {
+            return Err("The depth of the merkle sum tree doesn't match the expected depth");
+        }
+        Ok(())
+    }
     /// Builds a Merkle Sum Tree from a CSV file stored at `path`. The CSV file must be formatted as follows:
     ///
     /// `username,balance_<cryptocurrency>_<chain>,balance_<cryptocurrency>_<chain>,...`
@@ -99,7 +126,26 @@ impl<const N_CURRENCIES: usize, const N_BYTES: usize> MerkleSumTree<N_CURRENCIES
         Self::from_entries(entries, cryptocurrencies, true)
     }
 
+    /// Builds a Merkle Sum Tree from a vector of entries sorted by `username_as_big_uint`. The padding entries are appended after the sorted entries.
+    ///
+    /// The layout of the tree, and hence its root, doesn't depend on the order of `entries` but only on the set of users and their balances.
+    pub fn new_sorted(
+        mut entries: Vec<Entry<N_CURRENCIES>>,
+        cryptocurrencies: Vec<Cryptocurrency>,
+    ) -> Result<MerkleSumTree<N_CURRENCIES, N_BYTES>, Box<dyn std::error::Error>>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        entries.sort_by(|a, b| a.username_as_big_uint().cmp(b.username_as_big_uint()));
+
+        // `is_sorted` refers to the order of the usernames themselves, which is unrelated to the order of their hashes
+        Self::from_entries(entries, cryptocurrencies, false)
+    }
+
     /// Builds a Merkle Sum Tree from a vector of entries
+    ///
+    /// If the `reject-duplicate-users` feature is enabled, returns an error if two entries share the same username. Zero entries are ignored by this check as they are legitimately repeated as padding.
     pub fn from_entries(
         mut entries: Vec<Entry<N_CURRENCIES>>,
         cryptocurrencies: Vec<Cryptocurrency>,
@@ -109,6 +155,33 @@ impl<const N_CURRENCIES: usize, const N_BYTES: usize> MerkleSumTree<N_CURRENCIES
         [usize; N_CURRENCIES + 1]: Sized,
         [usize; N_CURRENCIES + 2]: Sized,
     {
+        if entries.is_empty() {
+            return Err(Box::new(EmptyEntries));
+        }
+
+        #[cfg(feature = "reject-duplicate-users")]
+        {
+            let zero_entry = Entry::<N_CURRENCIES>::zero_entry();
+            let mut indices_by_username = std::collections::HashMap::new();
+
+            for (index, entry) in entries.iter().enumerate() {
+                if *entry == zero_entry {
+                    continue;
+                }
+                if let Some(first_index) =
+                    indices_by_username.insert(entry.username_as_big_uint(), index)
+                {
+                    return Err(format!(
+                        "Duplicate username {} at indices {} and {}",
+                        entry.username(),
+                        first_index,
+                        index
+                    )
+                    .into());
+                }
+            }
+        }
+
         let depth = (entries.len() as f64).log2().ceil() as usize;
 
         // Pad the entries with empty entries to make the number of entries equal to 2^depth
@@ -133,6 +206,61 @@ impl<const N_CURRENCIES: usize, const N_BYTES: usize> MerkleSumTree<N_CURRENCIES
         })
     }
 
+    /// Builds a Merkle Sum Tree from a vector of anonymous entries, whose leaves are identified by a salt rather than a username
+    pub fn from_anon_entries(
+        anon_entries: Vec<AnonEntry<N_CURRENCIES>>,
+        cryptocurrencies: Vec<Cryptocurrency>,
+    ) -> Result<MerkleSumTree<N_CURRENCIES, N_BYTES>, Box<dyn std::error::Error>>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        let entries = anon_entries.into_iter().map(Entry::from).collect();
+        Self::from_entries(entries, cryptocurrencies, false)
+    }
+
+    /// Builds the top of a Merkle Sum Tree of the given `depth` without holding all its levels in memory.
+    ///
+    /// The entries are consumed in chunks of `chunk_size` entries, which must be a power of 2. The subtree of each chunk is built and discarded once its root is computed, so that only one chunk is in memory at a time.
+    /// If `entries` yields less than `2^depth` entries, the tree is padded with zero entries.
+    ///
+    /// Returns the root and the levels of the tree from the chunk roots up to the root. The lower levels of a chunk can be rebuilt on demand to generate proofs for its entries.
+    pub fn build_chunked<I: IntoIterator<Item = Entry<N_CURRENCIES>>>(
+        entries: I,
+        depth: usize,
+        chunk_size: usize,
+    ) -> Result<(Node<N_CURRENCIES>, Vec<Vec<Node<N_CURRENCIES>>>), Box<dyn std::error::Error>>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        let n_leaves = 2usize.pow(depth as u32);
+        if !chunk_size.is_power_of_two() || chunk_size > n_leaves {
+            return Err(
+                "Chunk size must be a power of 2 not larger than the number of leaves".into(),
+            );
+        }
+
+        let chunk_depth = chunk_size.trailing_zeros() as usize;
+        let mut entries = entries.into_iter();
+        let mut chunk_roots = Vec::with_capacity(n_leaves / chunk_size);
+
+        for _ in 0..n_leaves / chunk_size {
+            let mut chunk: Vec<Entry<N_CURRENCIES>> = entries.by_ref().take(chunk_size).collect();
+            chunk.resize(chunk_size, Entry::zero_entry());
+
+            let leaves = build_leaves_from_entries(&chunk);
+            let (chunk_root, _) = build_merkle_tree_from_leaves(&leaves, chunk_depth)?;
+            chunk_roots.push(chunk_root);
+        }
+
+        if entries.next().is_some() {
+            return Err("Too many entries for the given depth".into());
+        }
+
+        build_merkle_tree_from_leaves(&chunk_roots, depth - chunk_depth)
+    }
+
     /// Builds a Merkle Sum Tree from a root node, a vector of nodes, a depth, a vector of entries, a vector of cryptocurrencies and a boolean indicating whether the leaves are sorted by the username byte values.
     pub fn from_params(
         root: Node<N_CURRENCIES>,
@@ -203,6 +331,255 @@ impl<const N_CURRENCIES: usize, const N_BYTES: usize> MerkleSumTree<N_CURRENCIES
         Ok(root)
     }
 
+    /// Recomputes the subtree whose root is the node at `level` and `index` from its leaves upward, then the nodes on the path from the subtree to the root of the tree, and returns the new root.
+    ///
+    /// This allows to fix a partially corrupted tree, e.g. loaded with `from_params`, as long as the leaves under the subtree are intact. A leaf itself (level 0) is recomputed from its entry.
+    /// Returns an error if there is no node at `level` and `index`.
+    pub fn repair_subtree(
+        &mut self,
+        level: usize,
+        index: usize,
+    ) -> Result<Node<N_CURRENCIES>, Box<dyn std::error::Error>>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        if level > self.depth || index >= 1 << (self.depth - level) {
+            return Err(format!("There is no node at level {} and index {}", level, index).into());
+        }
+
+        if level == 0 {
+            self.nodes[0][index] = self.entries[index].compute_leaf();
+        }
+
+        // The nodes of the subtree, level by level from the leaves
+        for subtree_level in 1..=level {
+            let width = level - subtree_level;
+            for node_index in (index << width)..((index + 1) << width) {
+                let node = Node::middle(
+                    &self.nodes[subtree_level - 1][2 * node_index],
+                    &self.nodes[subtree_level - 1][2 * node_index + 1],
+                );
+                self.nodes[subtree_level][node_index] = node;
+            }
+        }
+
+        // The ancestors of the subtree
+        let mut current_index = index;
+        for ancestor_level in level + 1..=self.depth {
+            let parent_index = current_index / 2;
+            let node = Node::middle(
+                &self.nodes[ancestor_level - 1][2 * parent_index],
+                &self.nodes[ancestor_level - 1][2 * parent_index + 1],
+            );
+            self.nodes[ancestor_level][parent_index] = node;
+            current_index = parent_index;
+        }
+
+        self.root = self.nodes[self.depth][0].clone();
+        Ok(self.root.clone())
+    }
+
+    /// Writes the entries of the tree to a CSV file stored at `path`, in leaf order and excluding the padding entries. The CSV file is formatted as expected by `from_csv`:
+    ///
+    /// `username,balance_<cryptocurrency>_<chain>,balance_<cryptocurrency>_<chain>,...`
+    ///
+    /// `dxGaEAii,11888,41163`
+    ///
+    /// The decimals of a cryptocurrency are appended to its column name, as in `balance_<cryptocurrency>_<chain>_<decimals>`, unless they are 0.
+    ///
+    /// Returns an error if any entry has a nonce, as the CSV format doesn't hold nonces and a tree built from the exported file wouldn't have the same root.
+    pub fn export_entries_csv(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if self.entries.iter().any(|entry| entry.nonce().is_some()) {
+            return Err(Box::from("Entries with a nonce can't be exported to CSV"));
+        }
+
+        let mut wtr = csv::Writer::from_path(path)?;
+
+        let mut headers = vec!["username".to_string()];
+        for cryptocurrency in &self.cryptocurrencies {
+            let mut header = format!("balance_{}_{}", cryptocurrency.name, cryptocurrency.chain);
+            if cryptocurrency.decimals != 0 {
+                header.push_str(&format!("_{}", cryptocurrency.decimals));
+            }
+            headers.push(header);
+        }
+        wtr.write_record(&headers)?;
+
+        let zero_entry = Entry::<N_CURRENCIES>::zero_entry();
+
+        for entry in self.entries.iter().filter(|entry| **entry != zero_entry) {
+            let mut record = vec![entry.username().to_string()];
+            record.extend(entry.balances().iter().map(|balance| balance.to_string()));
+            wtr.write_record(&record)?;
+        }
+
+        wtr.flush()?;
+
+        Ok(())
+    }
+
+    /// Merges two Merkle Sum Trees built over the same set of users into a new tree.
+    ///
+    /// The entries of the merged tree keep the leaf order of `self` and their balances are equal to the sum of the balances of the entries at the same leaf index in `self` and `other`.
+    ///
+    /// Returns an error if the trees have different depths or cryptocurrencies, if the usernames don't match at any leaf index or if any of the merged balances doesn't lie in the range defined by `N_BYTES`.
+    pub fn merge(&self, other: &Self) -> Result<Self, &'static str>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        if self.depth != other.depth {
+            return Err("Trees have different depths");
+        }
+
+        if self.cryptocurrencies != other.cryptocurrencies {
+            return Err("Trees have different cryptocurrencies");
+        }
+
+        let mut entries = Vec::with_capacity(self.entries.len());
+
+        for (entry, other_entry) in self.entries.iter().zip(other.entries.iter()) {
+            if entry.username_as_big_uint() != other_entry.username_as_big_uint() {
+                return Err("Trees have different usernames at the same leaf index");
+            }
+
+            let merged_balances: [BigUint; N_CURRENCIES] =
+                std::array::from_fn(|i| &entry.balances()[i] + &other_entry.balances()[i]);
+
+            let mut merged_entry = entry.clone();
+            merged_entry.recompute_leaf(&merged_balances);
+            entries.push(merged_entry);
+        }
+
+        let merged_tree =
+            Self::from_entries(entries, self.cryptocurrencies.clone(), self.is_sorted)
+                .map_err(|_| "Failed to build the merged tree")?;
+
+        // The root balances are the largest balances in the tree, so checking them bounds every node
+        let max_balance = BigUint::from(1u8) << (N_BYTES * 8);
+        if merged_tree
+            .root
+            .balances
+            .iter()
+            .any(|balance| fp_to_big_uint(*balance) >= max_balance)
+        {
+            return Err("Merged balances exceed the range defined by N_BYTES");
+        }
+
+        Ok(merged_tree)
+    }
+
+    /// Splits the entries into `n_shards` trees of equal size, which can be built and proven on separate machines and combined with `combine_shard_roots`.
+    ///
+    /// The entries are padded with zero entries to a power of 2 and split in leaf order, so that the combined root is equal to the root of a single tree built from the same entries.
+    /// `n_shards` must be a power of 2 not larger than the padded number of entries.
+    pub fn shard_entries(
+        mut entries: Vec<Entry<N_CURRENCIES>>,
+        cryptocurrencies: Vec<Cryptocurrency>,
+        n_shards: usize,
+    ) -> Result<Vec<Self>, Box<dyn std::error::Error>>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        if entries.is_empty() {
+            return Err(Box::new(EmptyEntries));
+        }
+
+        let n_leaves = entries.len().next_power_of_two();
+        if !n_shards.is_power_of_two() || n_shards > n_leaves {
+            return Err(Box::from(
+                "Number of shards must be a power of 2 not larger than the number of leaves",
+            ));
+        }
+
+        entries.resize(n_leaves, Entry::zero_entry());
+
+        entries
+            .chunks(n_leaves / n_shards)
+            .map(|shard| Self::from_entries(shard.to_vec(), cryptocurrencies.clone(), false))
+            .collect()
+    }
+
+    /// Builds the top of the tree over the roots of the shards returned by `shard_entries`, in order, and returns its root.
+    ///
+    /// Returns an error if the number of shards is not a power of 2, if the shards have different depths or cryptocurrencies or if any of the combined balances doesn't lie in the range defined by `N_BYTES`.
+    pub fn combine_shard_roots(
+        shards: &[Self],
+    ) -> Result<Node<N_CURRENCIES>, Box<dyn std::error::Error>>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        if shards.is_empty() {
+            return Err(Box::new(EmptyEntries));
+        }
+
+        if !shards.len().is_power_of_two() {
+            return Err(Box::from("Number of shards must be a power of 2"));
+        }
+
+        if shards.iter().any(|shard| {
+            shard.depth != shards[0].depth || shard.cryptocurrencies != shards[0].cryptocurrencies
+        }) {
+            return Err(Box::from(
+                "Shards have different depths or cryptocurrencies",
+            ));
+        }
+
+        let shard_roots: Vec<Node<N_CURRENCIES>> =
+            shards.iter().map(|shard| shard.root.clone()).collect();
+        let top_depth = shards.len().trailing_zeros() as usize;
+        let (root, _) = build_merkle_tree_from_leaves(&shard_roots, top_depth)?;
+
+        // The root balances are the largest balances in the tree, so checking them bounds every node
+        let max_balance = BigUint::from(1u8) << (N_BYTES * 8);
+        if root
+            .balances
+            .iter()
+            .any(|balance| fp_to_big_uint(*balance) >= max_balance)
+        {
+            return Err(Box::from(
+                "Combined balances exceed the range defined by N_BYTES",
+            ));
+        }
+
+        Ok(root)
+    }
+
+    /// Returns the position `(level, index)` of the first node whose hash is `hash`, where level 0 is the level of the leaves and the root is at level `depth`.
+    ///
+    /// Useful to trace a hash that appears in a failing proof back to its position in the tree.
+    pub fn find_node(&self, hash: Fp) -> Option<(usize, usize)> {
+        self.nodes.iter().enumerate().find_map(|(level, nodes)| {
+            nodes
+                .iter()
+                .position(|node| node.hash == hash)
+                .map(|index| (level, index))
+        })
+    }
+
+    /// Returns true if the two trees have the same multiset of hashed usernames, regardless of the balances and of the order of the entries.
+    ///
+    /// Comparing the trees of two snapshots detects users dropped by the exchange in between. The padding entries are ignored, so trees padded to different sizes can be compared.
+    /// The nonces blinding the leaves aren't taken into account, so users whose nonces were rotated between the snapshots are still recognized.
+    pub fn same_users(&self, other: &Self) -> bool {
+        let sorted_hashed_usernames = |tree: &Self| {
+            let zero_entry = Entry::<N_CURRENCIES>::zero_entry();
+            let mut hashed_usernames: Vec<&BigUint> = tree
+                .entries
+                .iter()
+                .filter(|entry| **entry != zero_entry)
+                .map(|entry| entry.username_as_big_uint())
+                .collect();
+            hashed_usernames.sort_unstable();
+            hashed_usernames
+        };
+
+        sorted_hashed_usernames(self) == sorted_hashed_usernames(other)
+    }
+
     /// Returns the index of the leaf with the matching username
     pub fn index_of_username(&self, username: &str) -> Result<usize, Box<dyn std::error::Error>>
     where
@@ -0,0 +1,129 @@
+use crate::merkle_sum_tree::{Entry, MerkleProof, Node};
+use halo2_proofs::halo2curves::{bn256::Fr as Fp, ff::PrimeField};
+use num_bigint::BigUint;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Serializable representation of a `MerkleProof`. Field elements are encoded as the hex string of their `to_repr` bytes and balances as decimal strings.
+#[derive(Serialize, Deserialize)]
+struct SerializableMerkleProof {
+    username: String,
+    // The leaf identity of the entry, which differs from the hashed username if the entry has a nonce
+    hashed_username: String,
+    balances: Vec<String>,
+    root_hash: String,
+    root_balances: Vec<String>,
+    sibling_leaf_node_hash_preimage: Vec<String>,
+    sibling_middle_node_hash_preimages: Vec<Vec<String>>,
+    path_indices: Vec<String>,
+}
+
+fn fp_to_hex(fp: &Fp) -> String {
+    hex::encode(fp.to_repr())
+}
+
+fn fp_from_hex(hex_str: &str) -> Result<Fp, String> {
+    let bytes = hex::decode(hex_str).map_err(|e| e.to_string())?;
+    let repr: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| format!("Invalid field element length: {}", hex_str))?;
+    Option::<Fp>::from(Fp::from_repr(repr)).ok_or(format!("Invalid field element: {}", hex_str))
+}
+
+fn fps_from_hex<const N: usize>(hex_strs: &[String]) -> Result<[Fp; N], String> {
+    let fps = hex_strs
+        .iter()
+        .map(|hex_str| fp_from_hex(hex_str))
+        .collect::<Result<Vec<Fp>, String>>()?;
+    fps.try_into()
+        .map_err(|fps: Vec<Fp>| format!("Expected {} field elements, got {}", N, fps.len()))
+}
+
+fn big_uint_from_str(decimal: &str) -> Result<BigUint, String> {
+    BigUint::parse_bytes(decimal.as_bytes(), 10).ok_or(format!("Invalid balance: {}", decimal))
+}
+
+impl<const N_CURRENCIES: usize> Serialize for MerkleProof<N_CURRENCIES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SerializableMerkleProof {
+            username: self.entry.username().to_owned(),
+            hashed_username: self.entry.leaf_identity().to_str_radix(10),
+            balances: self
+                .entry
+                .balances()
+                .iter()
+                .map(|balance| balance.to_str_radix(10))
+                .collect(),
+            root_hash: fp_to_hex(&self.root.hash),
+            root_balances: self.root.balances.iter().map(fp_to_hex).collect(),
+            sibling_leaf_node_hash_preimage: self
+                .sibling_leaf_node_hash_preimage
+                .iter()
+                .map(fp_to_hex)
+                .collect(),
+            sibling_middle_node_hash_preimages: self
+                .sibling_middle_node_hash_preimages
+                .iter()
+                .map(|preimage| preimage.iter().map(fp_to_hex).collect())
+                .collect(),
+            path_indices: self.path_indices.iter().map(fp_to_hex).collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, const N_CURRENCIES: usize> Deserialize<'de> for MerkleProof<N_CURRENCIES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let proof = SerializableMerkleProof::deserialize(deserializer)?;
+
+        let balances = proof
+            .balances
+            .iter()
+            .map(|balance| big_uint_from_str(balance))
+            .collect::<Result<Vec<BigUint>, String>>()
+            .map_err(D::Error::custom)?
+            .try_into()
+            .map_err(|_| D::Error::custom("Invalid number of balances"))?;
+
+        let entry = Entry::from_hashed_username(
+            proof.username,
+            big_uint_from_str(&proof.hashed_username).map_err(D::Error::custom)?,
+            balances,
+        );
+
+        let root = Node {
+            hash: fp_from_hex(&proof.root_hash).map_err(D::Error::custom)?,
+            balances: fps_from_hex(&proof.root_balances).map_err(D::Error::custom)?,
+        };
+
+        let sibling_middle_node_hash_preimages = proof
+            .sibling_middle_node_hash_preimages
+            .iter()
+            .map(|preimage| fps_from_hex(preimage))
+            .collect::<Result<Vec<_>, String>>()
+            .map_err(D::Error::custom)?;
+
+        let path_indices = proof
+            .path_indices
+            .iter()
+            .map(|path_index| fp_from_hex(path_index))
+            .collect::<Result<Vec<_>, String>>()
+            .map_err(D::Error::custom)?;
+
+        Ok(MerkleProof {
+            entry,
+            root,
+            sibling_leaf_node_hash_preimage: fps_from_hex(&proof.sibling_leaf_node_hash_preimage)
+                .map_err(D::Error::custom)?,
+            sibling_middle_node_hash_preimages,
+            path_indices,
+        })
+    }
+}
@@ -1,8 +1,17 @@
 #[cfg(test)]
 mod test {
 
-    use crate::merkle_sum_tree::utils::big_uint_to_fp;
-    use crate::merkle_sum_tree::{Entry, MerkleSumTree, Node, Tree};
+    use crate::merkle_sum_tree::utils::{
+        big_uint_to_fp, build_merkle_tree_from_leaves, calculate_max_root_balance, check_solvency,
+        commitment_digest, export_public_inputs_csv, fp_to_big_uint, is_solvent, max_balance_bits,
+        min_n_bytes_for_entries, min_n_bytes_for_root, parse_csv_to_entries,
+        parse_csv_to_entries_with_options, root_from_penultimate, solvency_margin,
+        validate_csv_balances, CsvBalancesError, CsvParserOptions, RootAccumulator,
+    };
+    use crate::merkle_sum_tree::{
+        Cryptocurrency, EmptyEntries, Entry, MerkleProof, MerkleSumTree, Node, Tree,
+    };
+    use halo2_proofs::halo2curves::bn256::Fr as Fp;
     use num_bigint::{BigUint, ToBigUint};
     use rand::Rng as _;
 
@@ -65,6 +74,59 @@ mod test {
         assert!(!merkle_tree.verify_proof(&proof_invalid_2));
     }
 
+    #[test]
+    fn test_entry_with_nonce() {
+        let balances = [BigUint::from(100u32), BigUint::from(200u32)];
+
+        let entry = Entry::<N_CURRENCIES>::new_with_nonce(
+            "alice".to_string(),
+            Fp::from(1u64),
+            balances.clone(),
+        );
+        let other_nonce_entry = Entry::<N_CURRENCIES>::new_with_nonce(
+            "alice".to_string(),
+            Fp::from(2u64),
+            balances.clone(),
+        );
+        let same_nonce_entry = Entry::<N_CURRENCIES>::new_with_nonce(
+            "alice".to_string(),
+            Fp::from(1u64),
+            balances.clone(),
+        );
+        let plain_entry = Entry::<N_CURRENCIES>::new("alice".to_string(), balances);
+
+        assert_eq!(entry.nonce(), Some(&Fp::from(1u64)));
+        assert_eq!(plain_entry.nonce(), None);
+
+        // Only the leaf identity is blinded, the hashed username still identifies the user
+        assert_eq!(
+            entry.username_as_big_uint(),
+            plain_entry.username_as_big_uint()
+        );
+        assert_ne!(entry.leaf_identity(), plain_entry.leaf_identity());
+        assert_eq!(
+            plain_entry.leaf_identity(),
+            *plain_entry.username_as_big_uint()
+        );
+
+        // The same username and balances with different nonces produce different leaves
+        assert_ne!(
+            entry.compute_leaf().hash,
+            other_nonce_entry.compute_leaf().hash
+        );
+        assert_eq!(
+            entry.compute_leaf().hash,
+            same_nonce_entry.compute_leaf().hash
+        );
+
+        // Without the nonce, the username and the balances don't reveal the leaf
+        assert_ne!(entry.compute_leaf().hash, plain_entry.compute_leaf().hash);
+        assert_eq!(
+            entry.compute_leaf().balances,
+            plain_entry.compute_leaf().balances
+        );
+    }
+
     #[test]
     fn test_update_mst_leaf() {
         let merkle_tree_1 =
@@ -126,6 +188,933 @@ mod test {
         assert!(old_root_hash != new_root_hash);
     }
 
+    #[test]
+    fn test_new_sorted_mst() {
+        let (cryptocurrencies, entries) =
+            parse_csv_to_entries::<&str, N_CURRENCIES, N_BYTES>("../csv/entry_13.csv").unwrap();
+        let (shuffled_cryptocurrencies, shuffled_entries) =
+            parse_csv_to_entries::<&str, N_CURRENCIES, N_BYTES>("../csv/entry_13_shuffled.csv")
+                .unwrap();
+
+        let merkle_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            entries.clone(),
+            cryptocurrencies.clone(),
+            false,
+        )
+        .unwrap();
+        let shuffled_merkle_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            shuffled_entries.clone(),
+            shuffled_cryptocurrencies.clone(),
+            false,
+        )
+        .unwrap();
+
+        // The order of the entries affects the root of an unsorted tree
+        assert_ne!(merkle_tree.root().hash, shuffled_merkle_tree.root().hash);
+
+        let sorted_merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new_sorted(entries, cryptocurrencies).unwrap();
+        let sorted_shuffled_merkle_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::new_sorted(
+            shuffled_entries,
+            shuffled_cryptocurrencies,
+        )
+        .unwrap();
+
+        assert_eq!(
+            sorted_merkle_tree.root(),
+            sorted_shuffled_merkle_tree.root()
+        );
+
+        // The 13 users are sorted by hashed username, followed by the padding entries
+        let sorted_entries = sorted_merkle_tree.entries();
+        assert!(sorted_entries[..13]
+            .windows(2)
+            .all(|pair| pair[0].username_as_big_uint() < pair[1].username_as_big_uint()));
+        assert_eq!(
+            sorted_merkle_tree.padding_indices(),
+            (13..16).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_find_node() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        let leaf_hash = merkle_tree.leaves()[5].hash;
+        assert_eq!(merkle_tree.find_node(leaf_hash), Some((0, 5)));
+
+        let middle_node_hash = merkle_tree.nodes()[2][3].hash;
+        assert_eq!(merkle_tree.find_node(middle_node_hash), Some((2, 3)));
+
+        assert_eq!(
+            merkle_tree.find_node(merkle_tree.root().hash),
+            Some((*merkle_tree.depth(), 0))
+        );
+
+        assert_eq!(merkle_tree.find_node(Fp::from(1u64)), None);
+    }
+
+    #[test]
+    fn test_same_users() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        // Same users with different balances, or in a different order
+        let modified_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16_modified.csv")
+                .unwrap();
+        assert!(merkle_tree.same_users(&modified_tree));
+
+        let shuffled_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            merkle_tree.entries().iter().rev().cloned().collect(),
+            merkle_tree.cryptocurrencies().to_vec(),
+            false,
+        )
+        .unwrap();
+        assert!(merkle_tree.same_users(&shuffled_tree));
+
+        // The tree of the next snapshot silently drops the 4th user
+        let mut entries = merkle_tree.entries().to_vec();
+        entries.remove(3);
+        let dropped_user_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            entries,
+            merkle_tree.cryptocurrencies().to_vec(),
+            false,
+        )
+        .unwrap();
+        assert!(!merkle_tree.same_users(&dropped_user_tree));
+        assert!(!dropped_user_tree.same_users(&merkle_tree));
+
+        // The same users with rotated nonces
+        let with_nonces = |offset: u64| {
+            let entries = merkle_tree
+                .entries()
+                .iter()
+                .enumerate()
+                .map(|(i, entry)| {
+                    Entry::new_with_nonce(
+                        entry.username().to_string(),
+                        Fp::from(offset + i as u64),
+                        entry.balances().clone(),
+                    )
+                })
+                .collect();
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+                entries,
+                merkle_tree.cryptocurrencies().to_vec(),
+                false,
+            )
+            .unwrap()
+        };
+        let nonce_tree = with_nonces(1);
+        let rotated_nonce_tree = with_nonces(100);
+        assert_ne!(nonce_tree.root().hash, rotated_nonce_tree.root().hash);
+        assert!(nonce_tree.same_users(&rotated_nonce_tree));
+        assert!(nonce_tree.same_users(&merkle_tree));
+    }
+
+    #[test]
+    fn test_repair_subtree() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        // A node of the subtree at level 2 and index 1 is corrupted, as well as the nodes computed from it
+        let mut corrupted_nodes = merkle_tree.nodes().to_vec();
+        corrupted_nodes[1][2].balances[0] += Fp::from(1u64);
+        corrupted_nodes[2][1] = Node::middle(&corrupted_nodes[1][2], &corrupted_nodes[1][3]);
+        corrupted_nodes[3][0] = Node::middle(&corrupted_nodes[2][0], &corrupted_nodes[2][1]);
+        corrupted_nodes[4][0] = Node::middle(&corrupted_nodes[3][0], &corrupted_nodes[3][1]);
+
+        let mut corrupted_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_params(
+            corrupted_nodes[4][0].clone(),
+            corrupted_nodes,
+            *merkle_tree.depth(),
+            merkle_tree.entries().to_vec(),
+            merkle_tree.cryptocurrencies().to_vec(),
+            false,
+        )
+        .unwrap();
+        assert_ne!(corrupted_tree.root(), merkle_tree.root());
+
+        let repaired_root = corrupted_tree.repair_subtree(2, 1).unwrap();
+        assert_eq!(repaired_root, *merkle_tree.root());
+        assert_eq!(corrupted_tree.root(), merkle_tree.root());
+        assert_eq!(corrupted_tree.nodes(), merkle_tree.nodes());
+
+        // There is no subtree outside of the tree
+        assert!(corrupted_tree.repair_subtree(2, 4).is_err());
+        assert!(corrupted_tree.repair_subtree(5, 0).is_err());
+    }
+
+    #[test]
+    fn test_merge_mst() {
+        let merkle_tree_1 =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        // The second tree has the same users as the first tree, with the 7th entry having different balances
+        let merkle_tree_2 =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16_modified.csv")
+                .unwrap();
+
+        let merged_tree = merkle_tree_1.merge(&merkle_tree_2).unwrap();
+
+        assert_eq!(*merged_tree.depth(), *merkle_tree_1.depth());
+
+        // Each leaf balance should be the sum of the balances of the leaves at the same index
+        for i in 0..16 {
+            let merged_entry = merged_tree.get_entry(i);
+            assert_eq!(
+                merged_entry.username(),
+                merkle_tree_1.get_entry(i).username()
+            );
+            for currency in 0..N_CURRENCIES {
+                assert_eq!(
+                    merged_entry.balances()[currency],
+                    &merkle_tree_1.get_entry(i).balances()[currency]
+                        + &merkle_tree_2.get_entry(i).balances()[currency]
+                );
+                assert_eq!(
+                    merged_tree.leaves()[i].balances[currency],
+                    merkle_tree_1.leaves()[i].balances[currency]
+                        + merkle_tree_2.leaves()[i].balances[currency]
+                );
+            }
+        }
+
+        // The root balances should be the sum of the root balances of the two trees
+        for currency in 0..N_CURRENCIES {
+            assert_eq!(
+                merged_tree.root().balances[currency],
+                merkle_tree_1.root().balances[currency] + merkle_tree_2.root().balances[currency]
+            );
+        }
+
+        // should create valid proof for each entry in the merged tree and verify it
+        for i in 0..16 {
+            let proof = merged_tree.generate_proof(i).unwrap();
+            assert!(merged_tree.verify_proof(&proof));
+        }
+
+        // shouldn't merge trees with a different user set
+        let mut entries = merkle_tree_1.entries().to_vec();
+        entries[3] = Entry::new(
+            "non_existing_user".to_string(),
+            entries[3].balances().clone(),
+        );
+        let merkle_tree_3 = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            entries,
+            merkle_tree_1.cryptocurrencies().to_vec(),
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            merkle_tree_1.merge(&merkle_tree_3).err(),
+            Some("Trees have different usernames at the same leaf index")
+        );
+
+        // shouldn't merge trees with different depths
+        let merkle_tree_4 =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_17.csv").unwrap();
+        assert_eq!(
+            merkle_tree_1.merge(&merkle_tree_4).err(),
+            Some("Trees have different depths")
+        );
+    }
+
+    #[test]
+    fn test_export_entries_csv() {
+        // The tree built from entry_13.csv is padded with zero entries
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_13.csv").unwrap();
+
+        let path = std::env::temp_dir().join("summa_export_entries_13.csv");
+        let path = path.to_str().unwrap();
+
+        merkle_tree.export_entries_csv(path).unwrap();
+
+        // The padding entries should not be exported
+        let (_, exported_entries) =
+            parse_csv_to_entries::<&str, N_CURRENCIES, N_BYTES>(path).unwrap();
+        assert_eq!(exported_entries.len(), 13);
+        assert_eq!(exported_entries[..], merkle_tree.entries()[..13]);
+
+        // Building a tree from the exported csv should result in the same root
+        let round_trip_merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv(path).unwrap();
+        assert_eq!(round_trip_merkle_tree.root(), merkle_tree.root());
+        assert_eq!(
+            round_trip_merkle_tree.cryptocurrencies(),
+            merkle_tree.cryptocurrencies()
+        );
+
+        std::fs::remove_file(path).unwrap();
+
+        // The nonces aren't part of the CSV format, so a tree with nonce entries isn't exported
+        let mut entries = merkle_tree.entries()[..13].to_vec();
+        entries[0] = Entry::new_with_nonce(
+            entries[0].username().to_string(),
+            Fp::from(1u64),
+            entries[0].balances().clone(),
+        );
+        let nonce_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            entries,
+            merkle_tree.cryptocurrencies().to_vec(),
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            nonce_tree.export_entries_csv(path).unwrap_err().to_string(),
+            "Entries with a nonce can't be exported to CSV"
+        );
+    }
+
+    #[test]
+    fn test_proof_from_file() {
+        // The tree built from entry_13.csv is padded with zero entries
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_13.csv").unwrap();
+
+        let path = std::env::temp_dir().join("summa_tree_13.bin");
+        let path = path.to_str().unwrap();
+
+        merkle_tree.write_to_file(path).unwrap();
+
+        for index in 0..merkle_tree.leaves().len() {
+            let proof = merkle_tree.generate_proof(index).unwrap();
+            let file_proof =
+                MerkleSumTree::<N_CURRENCIES, N_BYTES>::proof_from_file(path, index).unwrap();
+
+            assert_eq!(file_proof.entry, proof.entry);
+            assert_eq!(file_proof.root, proof.root);
+            assert_eq!(
+                file_proof.sibling_leaf_node_hash_preimage,
+                proof.sibling_leaf_node_hash_preimage
+            );
+            assert_eq!(
+                file_proof.sibling_middle_node_hash_preimages,
+                proof.sibling_middle_node_hash_preimages
+            );
+            assert_eq!(file_proof.path_indices, proof.path_indices);
+            assert!(merkle_tree.verify_proof(&file_proof));
+        }
+
+        assert!(MerkleSumTree::<N_CURRENCIES, N_BYTES>::proof_from_file(path, 16).is_err());
+
+        // A tree file can't be read with a different number of currencies
+        assert!(MerkleSumTree::<3, N_BYTES>::proof_from_file(path, 0).is_err());
+
+        // A tree file with a header of depth 0 is rejected
+        let mut header = 0u64.to_le_bytes().to_vec();
+        header.extend_from_slice(&(N_CURRENCIES as u64).to_le_bytes());
+        std::fs::write(path, header).unwrap();
+        assert_eq!(
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::proof_from_file(path, 0)
+                .unwrap_err()
+                .to_string(),
+            "Tree file has a depth of 0"
+        );
+
+        // A tree file with a header depth that overflows the record offsets, or that the file is too short to hold, is rejected
+        let mut header = 64u64.to_le_bytes().to_vec();
+        header.extend_from_slice(&(N_CURRENCIES as u64).to_le_bytes());
+        std::fs::write(path, header).unwrap();
+        assert_eq!(
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::proof_from_file(path, 0)
+                .unwrap_err()
+                .to_string(),
+            "Tree file has an invalid depth of 64"
+        );
+
+        merkle_tree.write_to_file(path).unwrap();
+        let mut tree_file = std::fs::read(path).unwrap();
+        tree_file[0..8].copy_from_slice(&40u64.to_le_bytes());
+        std::fs::write(path, tree_file).unwrap();
+        assert_eq!(
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::proof_from_file(path, 0)
+                .unwrap_err()
+                .to_string(),
+            "Tree file is too short for a depth of 40"
+        );
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_empty_entries() {
+        let result = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(vec![], vec![], false);
+        assert!(result.unwrap_err().downcast_ref::<EmptyEntries>().is_some());
+
+        // A CSV file with a header and no entries
+        let path = std::env::temp_dir().join("summa_empty_entries.csv");
+        std::fs::write(&path, "username,balance_ETH_ETH,balance_USDT_ETH\n").unwrap();
+        let result = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv(path.to_str().unwrap());
+        assert!(result.unwrap_err().downcast_ref::<EmptyEntries>().is_some());
+        std::fs::remove_file(path).unwrap();
+
+        let result = build_merkle_tree_from_leaves::<N_CURRENCIES>(&[], 0);
+        assert!(result.unwrap_err().downcast_ref::<EmptyEntries>().is_some());
+    }
+
+    #[test]
+    fn test_root_accumulator() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        let mut accumulator = RootAccumulator::<N_CURRENCIES>::new();
+        for leaf in merkle_tree.leaves() {
+            accumulator.push(leaf.clone());
+        }
+        assert_eq!(accumulator.leaf_count(), 16);
+        assert_eq!(accumulator.finalize().unwrap(), *merkle_tree.root());
+
+        // The number of leaves must be a non zero power of 2
+        let mut accumulator = RootAccumulator::<N_CURRENCIES>::new();
+        for leaf in &merkle_tree.leaves()[..3] {
+            accumulator.push(leaf.clone());
+        }
+        assert!(accumulator.finalize().is_err());
+
+        let accumulator = RootAccumulator::<N_CURRENCIES>::new();
+        assert!(accumulator
+            .finalize()
+            .unwrap_err()
+            .downcast_ref::<EmptyEntries>()
+            .is_some());
+    }
+
+    #[test]
+    fn test_root_from_penultimate() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        let penultimate_level = &merkle_tree.nodes()[merkle_tree.depth() - 1];
+        assert_eq!(penultimate_level.len(), 2);
+
+        let root = root_from_penultimate(&penultimate_level[0], &penultimate_level[1]);
+        assert_eq!(&root, merkle_tree.root());
+
+        // Swapping the children doesn't reproduce the root
+        let swapped_root = root_from_penultimate(&penultimate_level[1], &penultimate_level[0]);
+        assert_ne!(swapped_root.hash, merkle_tree.root().hash);
+    }
+
+    #[test]
+    fn test_solvency_margin() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        let liabilities = merkle_tree.root().balances.map(fp_to_big_uint);
+
+        // The first currency is covered with a buffer of 1, the second one lacks 5
+        let asset_sums = [&liabilities[0] + 1u32, &liabilities[1] - 5u32];
+
+        assert_eq!(solvency_margin(&merkle_tree, &asset_sums), Some([1, -5]));
+
+        // A margin beyond the range of an i128 is reported rather than truncated
+        let large_asset_sums = [BigUint::from(1u8) << 128, liabilities[1].clone()];
+        assert_eq!(solvency_margin(&merkle_tree, &large_asset_sums), None);
+    }
+
+    #[test]
+    fn test_check_solvency() {
+        let balances = |balances: [u64; 3]| balances.map(BigUint::from);
+        let entries = vec![
+            Entry::new("alice".to_string(), balances([10, 20, 30])),
+            Entry::new("bob".to_string(), balances([5, 5, 5])),
+            Entry::new("carol".to_string(), balances([1, 2, 3])),
+            Entry::new("dave".to_string(), balances([4, 3, 2])),
+        ];
+        let cryptocurrencies = ["BTC", "ETH", "USDT"]
+            .iter()
+            .map(|name| Cryptocurrency {
+                name: name.to_string(),
+                chain: name.to_string(),
+                decimals: 0,
+            })
+            .collect();
+
+        let merkle_tree =
+            MerkleSumTree::<3, N_BYTES>::from_entries(entries, cryptocurrencies, false).unwrap();
+
+        // The liabilities are [20, 30, 40]
+        assert_eq!(
+            check_solvency(&merkle_tree, &balances([20, 30, 40])),
+            Ok(())
+        );
+        assert_eq!(
+            check_solvency(&merkle_tree, &balances([19, 1000, 39])),
+            Err(vec![0, 2])
+        );
+    }
+
+    #[test]
+    fn test_is_solvent() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+        let liabilities = merkle_tree.root().balances.map(fp_to_big_uint);
+
+        let covering_sums = liabilities.clone().map(|liability| liability + 1u32);
+        assert!(is_solvent(&merkle_tree, &covering_sums));
+        assert!(is_solvent(&merkle_tree, &liabilities));
+
+        let short_sums = liabilities.map(|liability| liability - 1u32);
+        assert!(!is_solvent(&merkle_tree, &short_sums));
+    }
+
+    #[test]
+    fn test_merkle_proof_path_eq() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        let proof = merkle_tree.generate_proof(0).unwrap();
+        assert!(proof.path_eq(&proof.clone()));
+
+        // Swapping a path index changes the path
+        let mut swapped_proof = proof.clone();
+        swapped_proof.path_indices[1] = Fp::one() - swapped_proof.path_indices[1];
+        assert!(!proof.path_eq(&swapped_proof));
+
+        // The proof of another user follows a different path
+        assert!(!proof.path_eq(&merkle_tree.generate_proof(1).unwrap()));
+    }
+
+    #[test]
+    fn test_export_public_inputs_csv() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        let proofs: Vec<MerkleProof<N_CURRENCIES>> = (0..4)
+            .map(|index| merkle_tree.generate_proof(index).unwrap())
+            .collect();
+
+        let path = std::env::temp_dir().join("summa_public_inputs.csv");
+        let path = path.to_str().unwrap();
+
+        export_public_inputs_csv(&proofs, path).unwrap();
+
+        let parse_hex = |hex: &str| {
+            big_uint_to_fp(
+                &BigUint::parse_bytes(hex.trim_start_matches("0x").as_bytes(), 16).unwrap(),
+            )
+        };
+
+        let mut rdr = csv::Reader::from_path(path).unwrap();
+        let records: Vec<csv::StringRecord> = rdr.records().map(|record| record.unwrap()).collect();
+        assert_eq!(records.len(), 4);
+
+        for (record, proof) in records.iter().zip(proofs.iter()) {
+            assert_eq!(&record[0], proof.entry.username());
+            assert_eq!(parse_hex(&record[1]), proof.entry.compute_leaf().hash);
+            assert_eq!(parse_hex(&record[2]), merkle_tree.root().hash);
+        }
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_shard_entries() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        let (cryptocurrencies, entries) =
+            parse_csv_to_entries::<_, N_CURRENCIES, N_BYTES>("../csv/entry_16.csv").unwrap();
+
+        let shards = MerkleSumTree::<N_CURRENCIES, N_BYTES>::shard_entries(
+            entries.clone(),
+            cryptocurrencies.clone(),
+            4,
+        )
+        .unwrap();
+
+        assert_eq!(shards.len(), 4);
+        for shard in &shards {
+            assert_eq!(*shard.depth(), 2);
+        }
+
+        let root = MerkleSumTree::<N_CURRENCIES, N_BYTES>::combine_shard_roots(&shards).unwrap();
+        assert_eq!(root, *merkle_tree.root());
+
+        // The number of shards must be a power of 2
+        assert!(MerkleSumTree::<N_CURRENCIES, N_BYTES>::shard_entries(
+            entries.clone(),
+            cryptocurrencies.clone(),
+            3
+        )
+        .is_err());
+        assert!(MerkleSumTree::<N_CURRENCIES, N_BYTES>::combine_shard_roots(&shards[..3]).is_err());
+
+        // The combined balances must lie in the range defined by N_BYTES
+        let shards =
+            MerkleSumTree::<N_CURRENCIES, 2>::shard_entries(entries, cryptocurrencies, 4).unwrap();
+        assert!(MerkleSumTree::<N_CURRENCIES, 2>::combine_shard_roots(&shards).is_err());
+    }
+
+    #[test]
+    fn test_padding_indices() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        // A tree with no padding should report no padding indices
+        assert!(merkle_tree.padding_indices().is_empty());
+        assert_eq!(merkle_tree.real_entry_count(), 16);
+
+        // Build a tree from 11 entries, padded to 16 leaves
+        let entries = merkle_tree.entries()[..11].to_vec();
+        let padded_merkle_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            entries,
+            merkle_tree.cryptocurrencies().to_vec(),
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(padded_merkle_tree.leaves().len(), 16);
+        assert_eq!(
+            padded_merkle_tree.padding_indices(),
+            (11..16).collect::<Vec<usize>>()
+        );
+        assert_eq!(padded_merkle_tree.real_entry_count(), 11);
+    }
+
+    #[cfg(feature = "reject-duplicate-users")]
+    #[test]
+    fn test_reject_duplicate_users() {
+        // The 5th entry of the csv has the same username as the 2nd entry
+        let result =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16_duplicate.csv");
+
+        match result {
+            Err(e) => assert_eq!(
+                e.to_string(),
+                "Duplicate username MBlfbBGI at indices 1 and 4"
+            ),
+            Ok(_) => panic!("Expected an error for the duplicate username"),
+        }
+
+        // Padding zero entries should not be considered duplicates
+        assert!(MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_13.csv").is_ok());
+    }
+
+    #[test]
+    fn test_parse_csv_mismatching_n_currencies() {
+        // The CSV file has balances for 3 cryptocurrencies
+        let result =
+            parse_csv_to_entries::<_, N_CURRENCIES, N_BYTES>("../csv/entry_4_three_currencies.csv");
+        match result {
+            Err(e) => assert_eq!(
+                e.to_string(),
+                "The CSV file has balances for 3 cryptocurrencies, expected N_CURRENCIES = 2"
+            ),
+            Ok(_) => panic!("Expected an error for the mismatching number of cryptocurrencies"),
+        }
+
+        let (cryptocurrencies, entries) =
+            parse_csv_to_entries::<_, 3, N_BYTES>("../csv/entry_4_three_currencies.csv").unwrap();
+        assert_eq!(cryptocurrencies.len(), 3);
+        assert_eq!(entries.len(), 4);
+    }
+
+    #[test]
+    fn test_parse_csv_missing_balance() {
+        // The row of carol, on line 4, has a single balance
+        let result =
+            parse_csv_to_entries::<_, N_CURRENCIES, N_BYTES>("../csv/entry_4_missing_balance.csv");
+        match result {
+            Err(e) => assert_eq!(e.to_string(), "Line 4 has 1 balances, expected 2"),
+            Ok(_) => panic!("Expected an error for the row missing a balance"),
+        }
+    }
+
+    #[test]
+    fn test_parse_csv_multichain() {
+        // USDT is held both on ETH and on TRON
+        let (cryptocurrencies, entries) =
+            parse_csv_to_entries::<_, 3, N_BYTES>("../csv/entry_4_multichain.csv").unwrap();
+
+        let currencies: Vec<(&str, &str)> = cryptocurrencies
+            .iter()
+            .map(|c| (c.name.as_str(), c.chain.as_str()))
+            .collect();
+        assert_eq!(
+            currencies,
+            vec![("USDT", "ETH"), ("USDT", "TRON"), ("ETH", "ETH")]
+        );
+
+        // The balances on each chain are tracked separately
+        assert_eq!(
+            entries[0].balances(),
+            &[
+                BigUint::from(100u32),
+                BigUint::from(50u32),
+                BigUint::from(1u32)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_csv_with_decimals() {
+        let (cryptocurrencies, entries) =
+            parse_csv_to_entries::<_, N_CURRENCIES, N_BYTES>("../csv/entry_16_decimals.csv")
+                .unwrap();
+
+        assert_eq!(
+            cryptocurrencies,
+            vec![
+                Cryptocurrency {
+                    name: "ETH".to_string(),
+                    chain: "ETH".to_string(),
+                    decimals: 18,
+                },
+                Cryptocurrency {
+                    name: "USDT".to_string(),
+                    chain: "ETH".to_string(),
+                    decimals: 6,
+                },
+            ]
+        );
+
+        // The decimals don't affect the balances, which are expressed in base units
+        let (_, entries_without_decimals) =
+            parse_csv_to_entries::<_, N_CURRENCIES, N_BYTES>("../csv/entry_16.csv").unwrap();
+        assert_eq!(entries, entries_without_decimals);
+
+        // The decimals default to 0 when omitted from the header
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+        assert!(merkle_tree
+            .cryptocurrencies()
+            .iter()
+            .all(|cryptocurrency| cryptocurrency.decimals == 0));
+    }
+
+    #[test]
+    fn test_parse_csv_with_colliding_currencies() {
+        let path = "../csv/entry_4_mixed_case_currencies.csv";
+
+        // `balance_eth_ETH` and `balance_ETH_ETH` refer to the same cryptocurrency
+        let result = parse_csv_to_entries::<_, N_CURRENCIES, N_BYTES>(path);
+        match result {
+            Err(e) => assert_eq!(
+                e.to_string(),
+                "Columns balance_eth_ETH and balance_ETH_ETH refer to the same cryptocurrency"
+            ),
+            Ok(_) => panic!("Expected an error for the colliding columns"),
+        }
+
+        // When collisions are allowed, the colliding columns are aggregated into a single cryptocurrency
+        let options = CsvParserOptions {
+            allow_collisions: true,
+            ..Default::default()
+        };
+        let (cryptocurrencies, entries) =
+            parse_csv_to_entries_with_options::<_, N_CURRENCIES, N_BYTES>(path, &options).unwrap();
+
+        // The names and chains are compared case-insensitively but keep the casing of their first column
+        assert_eq!(cryptocurrencies[0].name, "eth");
+        assert_eq!(cryptocurrencies[0].chain, "ETH");
+        assert_eq!(cryptocurrencies[1].name, "usdt");
+        assert_eq!(cryptocurrencies[1].chain, "eth");
+
+        let eth_balances: Vec<BigUint> = entries
+            .iter()
+            .map(|entry| entry.balances()[0].clone())
+            .collect();
+        assert_eq!(
+            eth_balances,
+            vec![
+                BigUint::from(15u32),
+                BigUint::from(7u32),
+                BigUint::from(3u32),
+                BigUint::from(2u32)
+            ]
+        );
+        assert_eq!(entries[3].balances()[1], BigUint::from(400u32));
+
+        // When names are case sensitive, the columns refer to different cryptocurrencies
+        let options = CsvParserOptions {
+            case_insensitive: false,
+            allow_collisions: false,
+        };
+        let result = parse_csv_to_entries_with_options::<_, 3, N_BYTES>(path, &options);
+        assert_eq!(result.unwrap().0[0].name, "eth");
+    }
+
+    #[test]
+    fn test_max_balance_bits() {
+        // The largest balance is 0x1_0000, which has 17 bits
+        let entries = vec![
+            Entry::new(
+                "alice".to_string(),
+                [BigUint::from(100u32), BigUint::from(0xffffu32)],
+            ),
+            Entry::new(
+                "bob".to_string(),
+                [BigUint::from(0x1_0000u32), BigUint::from(5u32)],
+            ),
+        ];
+
+        assert_eq!(max_balance_bits(&entries), 17);
+        assert_eq!(min_n_bytes_for_entries(&entries), 3);
+
+        assert_eq!(max_balance_bits(&[Entry::<N_CURRENCIES>::zero_entry()]), 0);
+    }
+
+    #[test]
+    fn test_min_n_bytes() {
+        // The largest balance is 2^24 - 1, which needs exactly 3 bytes
+        let entries = vec![
+            Entry::new(
+                "alice".to_string(),
+                [BigUint::from(100u32), BigUint::from(16777215u32)],
+            ),
+            Entry::new(
+                "bob".to_string(),
+                [BigUint::from(65536u32), BigUint::from(5u32)],
+            ),
+        ];
+
+        assert_eq!(min_n_bytes_for_entries(&entries), 3);
+
+        // The total balance of the second currency is 16777220, which is covered by the max root balance for 3 bytes and 1 level but not for 2 bytes
+        assert_eq!(min_n_bytes_for_root(&entries), 3);
+        assert!(calculate_max_root_balance(2, 1) < BigUint::from(16777220u32));
+
+        // A dataset of zero balances still needs one byte
+        assert_eq!(
+            min_n_bytes_for_entries(&[Entry::<N_CURRENCIES>::zero_entry()]),
+            1
+        );
+    }
+
+    #[test]
+    fn test_merkle_proof_serialization() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        for i in 0..16 {
+            let proof = merkle_tree.generate_proof(i).unwrap();
+
+            let serialized = serde_json::to_string(&proof).unwrap();
+            let deserialized: MerkleProof<N_CURRENCIES> =
+                serde_json::from_str(&serialized).unwrap();
+
+            // The round-trip should preserve the proof
+            assert!(merkle_tree.verify_proof(&deserialized));
+            assert_eq!(deserialized.entry, proof.entry);
+            assert_eq!(deserialized.root, proof.root);
+            assert_eq!(serde_json::to_string(&deserialized).unwrap(), serialized);
+        }
+
+        // Malformed field elements should be rejected
+        let proof = merkle_tree.generate_proof(0).unwrap();
+        let mut json: serde_json::Value = serde_json::to_value(&proof).unwrap();
+        json["root_hash"] = serde_json::Value::String("zz".to_string());
+        assert!(serde_json::from_value::<MerkleProof<N_CURRENCIES>>(json).is_err());
+    }
+
+    #[test]
+    fn test_build_chunked() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_17.csv").unwrap();
+
+        let (_, entries) =
+            parse_csv_to_entries::<_, N_CURRENCIES, N_BYTES>("../csv/entry_17.csv").unwrap();
+
+        // The root should match the in-memory build for any chunk size, including a single chunk and single entry chunks
+        for chunk_size in [1, 4, 8, 32] {
+            let (root, nodes) = MerkleSumTree::<N_CURRENCIES, N_BYTES>::build_chunked(
+                entries.clone(),
+                *merkle_tree.depth(),
+                chunk_size,
+            )
+            .unwrap();
+
+            assert_eq!(root, *merkle_tree.root());
+            // Only the levels from the chunk roots up to the root are kept
+            assert_eq!(nodes[0].len(), 32 / chunk_size);
+        }
+
+        // Invalid chunk sizes should be rejected
+        assert!(MerkleSumTree::<N_CURRENCIES, N_BYTES>::build_chunked(
+            entries.clone(),
+            *merkle_tree.depth(),
+            3
+        )
+        .is_err());
+        assert!(MerkleSumTree::<N_CURRENCIES, N_BYTES>::build_chunked(
+            entries.clone(),
+            *merkle_tree.depth(),
+            64
+        )
+        .is_err());
+
+        // 17 entries don't fit in a tree of depth 4
+        assert!(MerkleSumTree::<N_CURRENCIES, N_BYTES>::build_chunked(entries, 4, 4).is_err());
+    }
+
+    #[test]
+    fn test_validate_csv_balances() {
+        // The first balance of the first entry is 2^112, which doesn't fit in 8 bytes
+        let result =
+            validate_csv_balances::<_, N_CURRENCIES>("../csv/entry_16_overflow.csv", Some(N_BYTES));
+        assert!(
+            matches!(result, Err(CsvBalancesError::InvalidCells(cells)) if cells == vec![(2, 0)])
+        );
+
+        // 2^112 doesn't wrap around the field modulus
+        assert!(
+            validate_csv_balances::<_, N_CURRENCIES>("../csv/entry_16_overflow.csv", None).is_ok()
+        );
+
+        assert!(
+            validate_csv_balances::<_, N_CURRENCIES>("../csv/entry_16.csv", Some(N_BYTES)).is_ok()
+        );
+
+        // The number of balance columns must match N_CURRENCIES
+        assert!(matches!(
+            validate_csv_balances::<_, 3>("../csv/entry_16.csv", None),
+            Err(CsvBalancesError::InvalidFile(_))
+        ));
+
+        // Rows with a missing or an extra balance are reported rather than aborting the check
+        let path = std::env::temp_dir().join("summa_ragged_balances.csv");
+        std::fs::write(
+            &path,
+            "username,balance_ETH_ETH,balance_USDT_ETH\nalice,1,2\nbob,3\ncarol,4,5,6\n",
+        )
+        .unwrap();
+        let result = validate_csv_balances::<_, N_CURRENCIES>(&path, None);
+        assert!(
+            matches!(result, Err(CsvBalancesError::InvalidCells(cells)) if cells == vec![(3, 1), (4, 2)])
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_commitment_digest() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        let timestamp = 1693559255;
+        let root = merkle_tree.root().clone();
+        let digest = commitment_digest(&root, timestamp);
+
+        assert_eq!(digest, commitment_digest(&root, timestamp));
+
+        // Changing any of the root balances should change the digest
+        for currency in 0..N_CURRENCIES {
+            let mut modified_root = root.clone();
+            modified_root.balances[currency] += Fp::from(1);
+            assert_ne!(commitment_digest(&modified_root, timestamp), digest);
+        }
+
+        // Changing the root hash or the timestamp should change the digest
+        let mut modified_root = root.clone();
+        modified_root.hash += Fp::from(1);
+        assert_ne!(commitment_digest(&modified_root, timestamp), digest);
+        assert_ne!(commitment_digest(&root, timestamp + 1), digest);
+    }
+
     #[test]
     fn test_big_uint_conversion() {
         let big_uint = 3.to_biguint().unwrap();
@@ -261,4 +1250,45 @@ mod test {
         // shouldn't create a proof for an entry that doesn't exist in the tree
         assert!(merkle_tree.generate_proof(32).is_err());
     }
+
+    // Builds the merkle sum tree from `entries` and asserts that its root balances match the grand sums computed by another subsystem for the same dataset
+    fn assert_grand_sum_matches_mst_root<const N_CURRENCIES: usize>(
+        entries: Vec<Entry<N_CURRENCIES>>,
+        cryptocurrencies: Vec<Cryptocurrency>,
+        grand_sums: &[BigUint],
+    ) where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(entries, cryptocurrencies, false)
+                .unwrap();
+
+        assert_eq!(grand_sums.len(), N_CURRENCIES);
+        for (currency, grand_sum) in grand_sums.iter().enumerate() {
+            assert_eq!(
+                fp_to_big_uint(merkle_tree.root().balances[currency]),
+                *grand_sum,
+                "Grand sum mismatch for {}",
+                merkle_tree.cryptocurrencies()[currency].name
+            );
+        }
+    }
+
+    #[test]
+    fn test_grand_sum_matches_mst_root() {
+        let (cryptocurrencies, entries) =
+            parse_csv_to_entries::<_, N_CURRENCIES, N_BYTES>("../csv/entry_16.csv").unwrap();
+
+        // Grand sums computed independently of the merkle sum tree
+        let mut grand_sums = vec![BigUint::from(0u8); N_CURRENCIES];
+        for entry in &entries {
+            for (currency, balance) in entry.balances().iter().enumerate() {
+                grand_sums[currency] += balance;
+            }
+        }
+        assert_eq!(grand_sums, vec![BigUint::from(556862u32); N_CURRENCIES]);
+
+        assert_grand_sum_matches_mst_root(entries, cryptocurrencies, &grand_sums);
+    }
 }
@@ -19,6 +19,29 @@ pub trait Tree<const N_CURRENCIES: usize> {
 
     fn get_entry(&self, index: usize) -> &Entry<N_CURRENCIES>;
 
+    /// Returns the indices of the leaves that are padding, namely the leaves equal to the zero leaf added to fill the tree up to a power of 2
+    fn padding_indices(&self) -> Vec<usize>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+    {
+        let zero_leaf = Entry::<N_CURRENCIES>::zero_entry().compute_leaf();
+
+        self.nodes()[0]
+            .iter()
+            .enumerate()
+            .filter(|(_, leaf)| **leaf == zero_leaf)
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Returns the number of entries of the tree that are not padding
+    fn real_entry_count(&self) -> usize
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+    {
+        self.nodes()[0].len() - self.padding_indices().len()
+    }
+
     /// Returns the hash preimage of a middle node.
     fn get_middle_node_hash_preimage(
         &self,
@@ -70,8 +93,8 @@ pub trait Tree<const N_CURRENCIES: usize> {
         // Constructing preimage
         let mut preimage = [Fp::zero(); N_CURRENCIES + 1];
 
-        // Add username to preimage
-        preimage[0] = big_uint_to_fp(&entry.username_as_big_uint());
+        // Add the leaf identity of the user to preimage
+        preimage[0] = big_uint_to_fp(&entry.leaf_identity());
 
         // Add balances to preimage
         for (i, balance) in preimage.iter_mut().enumerate().skip(1).take(N_CURRENCIES) {
@@ -0,0 +1,227 @@
+use crate::merkle_sum_tree::utils::{big_uint_to_fp, fp_to_big_uint};
+use crate::merkle_sum_tree::{Entry, MerkleProof, MerkleSumTree, Node, Tree};
+use halo2_proofs::halo2curves::{bn256::Fr as Fp, ff::PrimeField};
+use num_bigint::BigUint;
+use std::fs::File;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+
+/// Maximum length in bytes of a username stored in a tree file
+const MAX_USERNAME_BYTES: usize = 64;
+
+const HEADER_SIZE: u64 = 16;
+const FP_SIZE: u64 = 32;
+// hashed username, username length and padded username
+const ENTRY_RECORD_SIZE: u64 = FP_SIZE + 2 + MAX_USERNAME_BYTES as u64;
+
+/// Reader over a tree file, reading only the records it is asked for.
+///
+/// A tree file is laid out as follows, with every integer encoded in little endian and every field element encoded as its `to_repr` bytes:
+/// * Header: the depth of the tree and `N_CURRENCIES`, as `u64`
+/// * Entry records, one per leaf: the leaf identity (the hashed username, blinded with the nonce of the entry if any) as 32 big endian bytes, the username length as `u16` and the username padded to `MAX_USERNAME_BYTES`
+/// * Node records, level by level from the leaves to the root: the node hash followed by the node balances
+struct TreeFile<const N_CURRENCIES: usize> {
+    file: File,
+    depth: usize,
+}
+
+impl<const N_CURRENCIES: usize> TreeFile<N_CURRENCIES> {
+    const NODE_RECORD_SIZE: u64 = FP_SIZE * (N_CURRENCIES as u64 + 1);
+
+    fn open(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut file = File::open(path)?;
+
+        let mut header = [0u8; HEADER_SIZE as usize];
+        file.read_exact(&mut header)?;
+        let depth = u64::from_le_bytes(header[0..8].try_into().unwrap()) as usize;
+        let n_currencies = u64::from_le_bytes(header[8..16].try_into().unwrap()) as usize;
+
+        if n_currencies != N_CURRENCIES {
+            return Err(format!(
+                "Tree file has {} currencies, expected {}",
+                n_currencies, N_CURRENCIES
+            )
+            .into());
+        }
+
+        // A tree has at least two leaves, a depth-0 file has no sibling leaf to read
+        if depth == 0 {
+            return Err(Box::from("Tree file has a depth of 0"));
+        }
+
+        // The depth is read from the file, it must neither overflow the record offsets nor exceed the records the file holds
+        if depth > 63 {
+            return Err(format!("Tree file has an invalid depth of {}", depth).into());
+        }
+        let records_size = (1u128 << depth) * ENTRY_RECORD_SIZE as u128
+            + ((1u128 << (depth + 1)) - 1) * Self::NODE_RECORD_SIZE as u128;
+        if (file.metadata()?.len() as u128) < HEADER_SIZE as u128 + records_size {
+            return Err(format!("Tree file is too short for a depth of {}", depth).into());
+        }
+
+        Ok(TreeFile { file, depth })
+    }
+
+    fn read_fp(&mut self) -> Result<Fp, Box<dyn std::error::Error>> {
+        let mut repr = [0u8; FP_SIZE as usize];
+        self.file.read_exact(&mut repr)?;
+        Option::<Fp>::from(Fp::from_repr(repr)).ok_or_else(|| Box::from("Invalid field element"))
+    }
+
+    fn read_entry_identity(
+        &mut self,
+        index: usize,
+    ) -> Result<(String, BigUint), Box<dyn std::error::Error>> {
+        self.file.seek(SeekFrom::Start(
+            HEADER_SIZE + index as u64 * ENTRY_RECORD_SIZE,
+        ))?;
+
+        let mut record = [0u8; ENTRY_RECORD_SIZE as usize];
+        self.file.read_exact(&mut record)?;
+
+        let hashed_username = BigUint::from_bytes_be(&record[0..32]);
+        let username_len = u16::from_le_bytes([record[32], record[33]]) as usize;
+        if username_len > MAX_USERNAME_BYTES {
+            return Err(Box::from("Invalid username length"));
+        }
+        let username = String::from_utf8(record[34..34 + username_len].to_vec())?;
+
+        Ok((username, hashed_username))
+    }
+
+    fn read_node(
+        &mut self,
+        level: usize,
+        index: usize,
+    ) -> Result<Node<N_CURRENCIES>, Box<dyn std::error::Error>> {
+        // the number of nodes stored before `level`
+        let preceding_nodes: u64 = (0..level).map(|l| 1u64 << (self.depth - l)).sum();
+        let entries_size = (1u64 << self.depth) * ENTRY_RECORD_SIZE;
+
+        self.file.seek(SeekFrom::Start(
+            HEADER_SIZE + entries_size + (preceding_nodes + index as u64) * Self::NODE_RECORD_SIZE,
+        ))?;
+
+        let hash = self.read_fp()?;
+        let mut balances = [Fp::zero(); N_CURRENCIES];
+        for balance in balances.iter_mut() {
+            *balance = self.read_fp()?;
+        }
+
+        Ok(Node { hash, balances })
+    }
+}
+
+impl<const N_CURRENCIES: usize, const N_BYTES: usize> MerkleSumTree<N_CURRENCIES, N_BYTES> {
+    /// Writes the tree to `path` using fixed-size records, so that proofs can later be computed with `proof_from_file` without loading the whole tree in memory.
+    ///
+    /// Returns an error if any username is longer than `MAX_USERNAME_BYTES`.
+    pub fn write_to_file(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        writer.write_all(&(*self.depth() as u64).to_le_bytes())?;
+        writer.write_all(&(N_CURRENCIES as u64).to_le_bytes())?;
+
+        for entry in self.entries() {
+            let username = entry.username().as_bytes();
+            if username.len() > MAX_USERNAME_BYTES {
+                return Err(format!("Username {} is too long", entry.username()).into());
+            }
+
+            let hashed_username = entry.leaf_identity().to_bytes_be();
+            if hashed_username.len() > FP_SIZE as usize {
+                return Err(Box::from("Hashed username is longer than 32 bytes"));
+            }
+            let mut hashed_username_record = [0u8; FP_SIZE as usize];
+            hashed_username_record[FP_SIZE as usize - hashed_username.len()..]
+                .copy_from_slice(&hashed_username);
+
+            let mut username_record = [0u8; MAX_USERNAME_BYTES];
+            username_record[..username.len()].copy_from_slice(username);
+
+            writer.write_all(&hashed_username_record)?;
+            writer.write_all(&(username.len() as u16).to_le_bytes())?;
+            writer.write_all(&username_record)?;
+        }
+
+        for level in self.nodes() {
+            for node in level {
+                writer.write_all(&node.hash.to_repr())?;
+                for balance in node.balances.iter() {
+                    writer.write_all(&balance.to_repr())?;
+                }
+            }
+        }
+
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Computes the Merkle proof of the entry at `index` from a tree file written by `write_to_file`.
+    ///
+    /// Only the records on the path of the entry are read: the entries of the leaf and its sibling, the children of the sibling nodes and the root.
+    pub fn proof_from_file(
+        path: &str,
+        index: usize,
+    ) -> Result<MerkleProof<N_CURRENCIES>, Box<dyn std::error::Error>>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        let mut tree_file = TreeFile::<N_CURRENCIES>::open(path)?;
+        let depth = tree_file.depth;
+
+        if index >= 1usize << depth {
+            return Err(Box::from("Index out of bounds"));
+        }
+
+        let (username, hashed_username) = tree_file.read_entry_identity(index)?;
+        let leaf = tree_file.read_node(0, index)?;
+        let balances = leaf.balances.map(fp_to_big_uint);
+        let entry = Entry::from_hashed_username(username, hashed_username, balances);
+
+        let sibling_leaf_index = if index % 2 == 0 { index + 1 } else { index - 1 };
+        let (_, sibling_hashed_username) = tree_file.read_entry_identity(sibling_leaf_index)?;
+        let sibling_leaf = tree_file.read_node(0, sibling_leaf_index)?;
+
+        let mut sibling_leaf_node_hash_preimage = [Fp::zero(); N_CURRENCIES + 1];
+        sibling_leaf_node_hash_preimage[0] = big_uint_to_fp(&sibling_hashed_username);
+        sibling_leaf_node_hash_preimage[1..].copy_from_slice(&sibling_leaf.balances);
+
+        let mut sibling_middle_node_hash_preimages = Vec::with_capacity(depth - 1);
+        let mut path_indices = vec![Fp::zero(); depth];
+        let mut current_index = index;
+
+        for level in 0..depth {
+            let position = current_index % 2;
+            let sibling_index = current_index - position + (1 - position);
+
+            if level > 0 {
+                let left_child = tree_file.read_node(level - 1, 2 * sibling_index)?;
+                let right_child = tree_file.read_node(level - 1, 2 * sibling_index + 1)?;
+
+                let mut preimage = [Fp::zero(); N_CURRENCIES + 2];
+                for (i, balance) in preimage.iter_mut().enumerate().take(N_CURRENCIES) {
+                    *balance = left_child.balances[i] + right_child.balances[i];
+                }
+                preimage[N_CURRENCIES] = left_child.hash;
+                preimage[N_CURRENCIES + 1] = right_child.hash;
+
+                sibling_middle_node_hash_preimages.push(preimage);
+            }
+
+            path_indices[level] = Fp::from(position as u64);
+            current_index /= 2;
+        }
+
+        let root = tree_file.read_node(depth, 0)?;
+
+        Ok(MerkleProof {
+            entry,
+            root,
+            sibling_leaf_node_hash_preimage,
+            sibling_middle_node_hash_preimages,
+            path_indices,
+        })
+    }
+}
@@ -1,4 +1,4 @@
-use crate::merkle_sum_tree::{Entry, Node};
+use crate::merkle_sum_tree::{EmptyEntries, Entry, Node};
 use halo2_proofs::halo2curves::bn256::Fr as Fp;
 use rayon::prelude::*;
 
@@ -10,6 +10,10 @@ where
     [usize; N_CURRENCIES + 1]: Sized,
     [usize; N_CURRENCIES + 2]: Sized,
 {
+    if leaves.is_empty() {
+        return Err(Box::new(EmptyEntries));
+    }
+
     let mut tree: Vec<Vec<Node<N_CURRENCIES>>> = Vec::with_capacity(depth + 1);
 
     // the size of a leaf layer must be a power of 2
@@ -1,52 +1,162 @@
-use crate::merkle_sum_tree::{Cryptocurrency, Entry};
+use crate::merkle_sum_tree::{Cryptocurrency, Entry, MerkleProof};
+use halo2_proofs::halo2curves::{bn256::Fr as Fp, ff::PrimeField};
 use num_bigint::BigUint;
+use num_traits::Num;
 use std::collections::HashMap;
 use std::error::Error;
+use std::fmt;
 use std::fs::File;
 use std::path::Path;
 
+/// Options for grouping the balance columns of a CSV file into cryptocurrencies
+///
+/// * `case_insensitive`: Whether cryptocurrency names and chains are compared case-insensitively. Only the comparison is affected: a cryptocurrency keeps the casing of the first column that refers to it, as the names and chains end up committed on chain. Names and chains are always trimmed.
+/// * `allow_collisions`: Whether columns that refer to the same cryptocurrency (e.g. `balance_eth_ETH` and `balance_ETH_ETH` when compared case-insensitively) are aggregated into a single cryptocurrency. If false, such columns are rejected.
+#[derive(Debug, Clone)]
+pub struct CsvParserOptions {
+    pub case_insensitive: bool,
+    pub allow_collisions: bool,
+}
+
+impl Default for CsvParserOptions {
+    fn default() -> Self {
+        Self {
+            case_insensitive: true,
+            allow_collisions: false,
+        }
+    }
+}
+
+impl CsvParserOptions {
+    fn same_name(&self, a: &str, b: &str) -> bool {
+        if self.case_insensitive {
+            a.to_uppercase() == b.to_uppercase()
+        } else {
+            a == b
+        }
+    }
+}
+
 pub fn parse_csv_to_entries<P: AsRef<Path>, const N_CURRENCIES: usize, const N_BYTES: usize>(
     path: P,
+) -> Result<(Vec<Cryptocurrency>, Vec<Entry<N_CURRENCIES>>), Box<dyn Error>> {
+    parse_csv_to_entries_with_options::<P, N_CURRENCIES, N_BYTES>(
+        path,
+        &CsvParserOptions::default(),
+    )
+}
+
+/// Parses the entries of a CSV file, grouping the balance columns that refer to the same cryptocurrency according to `options`.
+///
+/// Balance columns are named `balance_<cryptocurrency>_<chain>` or `balance_<cryptocurrency>_<chain>_<decimals>`. The decimals of a cryptocurrency default to 0 when omitted.
+/// Every row must have a balance for every balance column, otherwise an error is returned with the line of the first offending row, the header being line 1.
+pub fn parse_csv_to_entries_with_options<
+    P: AsRef<Path>,
+    const N_CURRENCIES: usize,
+    const N_BYTES: usize,
+>(
+    path: P,
+    options: &CsvParserOptions,
 ) -> Result<(Vec<Cryptocurrency>, Vec<Entry<N_CURRENCIES>>), Box<dyn Error>> {
     let file = File::open(path)?;
-    let mut rdr = csv::ReaderBuilder::new().from_reader(file);
+    // Rows of unequal lengths are accepted by the reader and rejected below with a line-numbered error
+    let mut rdr = csv::ReaderBuilder::new().flexible(true).from_reader(file);
 
     let headers = rdr.headers()?.clone();
     let mut cryptocurrencies: Vec<Cryptocurrency> = Vec::with_capacity(N_CURRENCIES);
+    // The columns holding the balances of each cryptocurrency
+    let mut columns: Vec<Vec<String>> = Vec::with_capacity(N_CURRENCIES);
 
     // Extracting cryptocurrency names from column names
     for header in headers.iter().skip(1) {
         // Skipping 'username' column
         let parts: Vec<&str> = header.split('_').collect();
-        if parts.len() == 3 && parts[0] == "balance" {
-            cryptocurrencies.push(Cryptocurrency {
-                name: parts[1].to_owned(),
-                chain: parts[2].to_owned(),
-            });
+        if (parts.len() == 3 || parts.len() == 4) && parts[0] == "balance" {
+            let decimals = match parts.get(3) {
+                Some(decimals) => decimals
+                    .trim()
+                    .parse::<u8>()
+                    .map_err(|_| format!("Invalid decimals in header: {}", header))?,
+                None => 0,
+            };
+            let cryptocurrency = Cryptocurrency {
+                name: parts[1].trim().to_owned(),
+                chain: parts[2].trim().to_owned(),
+                decimals,
+            };
+
+            match cryptocurrencies.iter().position(|c| {
+                options.same_name(&c.name, &cryptocurrency.name)
+                    && options.same_name(&c.chain, &cryptocurrency.chain)
+            }) {
+                Some(index) if cryptocurrencies[index].decimals != cryptocurrency.decimals => {
+                    return Err(format!(
+                        "Columns {} and {} have different decimals",
+                        columns[index][0], header
+                    )
+                    .into())
+                }
+                Some(index) if options.allow_collisions => columns[index].push(header.to_owned()),
+                Some(index) => {
+                    return Err(format!(
+                        "Columns {} and {} refer to the same cryptocurrency",
+                        columns[index][0], header
+                    )
+                    .into())
+                }
+                None => {
+                    cryptocurrencies.push(cryptocurrency);
+                    columns.push(vec![header.to_owned()]);
+                }
+            }
         } else {
             // Throw an error if the header is malformed
             return Err(format!("Invalid header: {}", header).into());
         }
     }
 
+    if cryptocurrencies.len() != N_CURRENCIES {
+        return Err(format!(
+            "The CSV file has balances for {} cryptocurrencies, expected N_CURRENCIES = {}",
+            cryptocurrencies.len(),
+            N_CURRENCIES
+        )
+        .into());
+    }
+
     let mut entries = Vec::new();
 
-    for result in rdr.deserialize() {
-        let record: HashMap<String, String> = result?;
+    for result in rdr.records() {
+        let record = result?;
+        if record.len() != headers.len() {
+            let line = record
+                .position()
+                .map_or(0, |position| position.line() as usize);
+            return Err(format!(
+                "Line {} has {} balances, expected {}",
+                line,
+                record.len().saturating_sub(1),
+                headers.len() - 1
+            )
+            .into());
+        }
+
+        let record: HashMap<String, String> = record.deserialize(Some(&headers))?;
         let username = record.get("username").ok_or("Username not found")?.clone();
 
         let mut balances_big_int = Vec::new();
-        for cryptocurrency in &cryptocurrencies {
-            let balance_str = record
-                .get(format!("balance_{}_{}", cryptocurrency.name, cryptocurrency.chain).as_str())
-                .ok_or(format!(
+        for (cryptocurrency, columns) in cryptocurrencies.iter().zip(&columns) {
+            let mut balance = BigUint::from(0u8);
+            for column in columns {
+                let balance_str = record.get(column.as_str()).ok_or(format!(
                     "Balance for {} on {} not found",
                     cryptocurrency.name, cryptocurrency.chain
                 ))?;
-            let balance = BigUint::parse_bytes(balance_str.as_bytes(), 10).ok_or(format!(
-                "Invalid balance for {} on {}",
-                cryptocurrency.name, cryptocurrency.chain
-            ))?;
+                balance += BigUint::parse_bytes(balance_str.as_bytes(), 10).ok_or(format!(
+                    "Invalid balance for {} on {}",
+                    cryptocurrency.name, cryptocurrency.chain
+                ))?;
+            }
             balances_big_int.push(balance);
         }
 
@@ -57,3 +167,126 @@ pub fn parse_csv_to_entries<P: AsRef<Path>, const N_CURRENCIES: usize, const N_B
 
     Ok((cryptocurrencies, entries))
 }
+
+/// Error returned by `validate_csv_balances`
+///
+/// * `InvalidCells`: The `(line, currency_index)` pairs of the invalid balances, lines being numbered from 1, the header being line 1.
+/// * `InvalidFile`: The file can't be read or parsed, or its header doesn't have `N_CURRENCIES` balance columns.
+#[derive(Debug)]
+pub enum CsvBalancesError {
+    InvalidCells(Vec<(usize, usize)>),
+    InvalidFile(Box<dyn Error>),
+}
+
+impl fmt::Display for CsvBalancesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CsvBalancesError::InvalidCells(cells) => {
+                write!(f, "Invalid balances at (line, currency_index) {:?}", cells)
+            }
+            CsvBalancesError::InvalidFile(e) => write!(f, "Invalid CSV file: {}", e),
+        }
+    }
+}
+
+impl Error for CsvBalancesError {}
+
+/// Checks the balances of a CSV file without building the tree.
+///
+/// Returns `CsvBalancesError::InvalidCells` with the balances that would silently wrap around the field modulus, or that exceed `2^(8 * n_bytes)` if `n_bytes` is provided. Unparsable balances are reported as well.
+/// Rows with a missing balance report the index of every missing currency, and rows with extra cells report the indices of the extra cells.
+pub fn validate_csv_balances<P: AsRef<Path>, const N_CURRENCIES: usize>(
+    path: P,
+    n_bytes: Option<usize>,
+) -> Result<(), CsvBalancesError> {
+    let invalid_cells = find_invalid_csv_balances::<P, N_CURRENCIES>(path, n_bytes)
+        .map_err(CsvBalancesError::InvalidFile)?;
+
+    if invalid_cells.is_empty() {
+        Ok(())
+    } else {
+        Err(CsvBalancesError::InvalidCells(invalid_cells))
+    }
+}
+
+fn find_invalid_csv_balances<P: AsRef<Path>, const N_CURRENCIES: usize>(
+    path: P,
+    n_bytes: Option<usize>,
+) -> Result<Vec<(usize, usize)>, Box<dyn Error>> {
+    let file = File::open(path)?;
+    let mut rdr = csv::ReaderBuilder::new().flexible(true).from_reader(file);
+
+    let headers = rdr.headers()?.clone();
+    if headers.len() != N_CURRENCIES + 1 {
+        return Err(format!(
+            "Expected {} balance columns, found {}",
+            N_CURRENCIES,
+            headers.len().saturating_sub(1)
+        )
+        .into());
+    }
+
+    let modulus = BigUint::from_str_radix(&Fp::MODULUS[2..], 16)?;
+    let bound = match n_bytes {
+        Some(n_bytes) => std::cmp::min(modulus, BigUint::from(1u8) << (8 * n_bytes)),
+        None => modulus,
+    };
+
+    let mut invalid_cells = Vec::new();
+
+    for result in rdr.records() {
+        let record = result?;
+        let line = record
+            .position()
+            .map_or(0, |position| position.line() as usize);
+
+        // Skipping 'username' column, a ragged row is reported up to the longest of its cells and the header
+        let n_cells = std::cmp::max(N_CURRENCIES, record.len().saturating_sub(1));
+        for currency_index in 0..n_cells {
+            if currency_index >= N_CURRENCIES {
+                invalid_cells.push((line, currency_index));
+                continue;
+            }
+
+            match record
+                .get(currency_index + 1)
+                .and_then(|balance_str| BigUint::parse_bytes(balance_str.as_bytes(), 10))
+            {
+                Some(balance) if balance < bound => {}
+                _ => invalid_cells.push((line, currency_index)),
+            }
+        }
+    }
+
+    Ok(invalid_cells)
+}
+
+/// Writes the public inputs of the inclusion proofs to a CSV file stored at `path`, one row per proof:
+///
+/// `username,leaf_hash,root_hash`
+///
+/// The hashes are written as 0x-prefixed hex strings.
+pub fn export_public_inputs_csv<const N_CURRENCIES: usize>(
+    proofs: &[MerkleProof<N_CURRENCIES>],
+    path: &str,
+) -> Result<(), Box<dyn Error>>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    let mut wtr = csv::Writer::from_path(path)?;
+
+    wtr.write_record(["username", "leaf_hash", "root_hash"])?;
+
+    for proof in proofs {
+        wtr.write_record([
+            proof.entry.username().to_string(),
+            format!("{:?}", proof.entry.compute_leaf().hash),
+            format!("{:?}", proof.root.hash),
+        ])?;
+    }
+
+    wtr.flush()?;
+
+    Ok(())
+}
@@ -1,7 +1,12 @@
 mod build_tree;
 mod csv_parser;
 mod operation_helpers;
+mod root_accumulator;
 
 pub use build_tree::{build_leaves_from_entries, build_merkle_tree_from_leaves};
-pub use csv_parser::parse_csv_to_entries;
+pub use csv_parser::{
+    export_public_inputs_csv, parse_csv_to_entries, parse_csv_to_entries_with_options,
+    validate_csv_balances, CsvBalancesError, CsvParserOptions,
+};
 pub use operation_helpers::*;
+pub use root_accumulator::RootAccumulator;
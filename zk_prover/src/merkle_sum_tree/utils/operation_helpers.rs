@@ -1,5 +1,8 @@
+use crate::chips::poseidon::poseidon_spec::PoseidonSpec;
+use crate::merkle_sum_tree::{Entry, Node, Tree};
+use halo2_gadgets::poseidon::primitives::{self as poseidon, ConstantLength};
 use halo2_proofs::halo2curves::{bn256::Fr as Fp, group::ff::PrimeField};
-use num_bigint::BigUint;
+use num_bigint::{BigInt, BigUint};
 
 /// Return a BigUint representation of the username
 pub fn big_intify_username(username: &str) -> BigUint {
@@ -15,3 +18,130 @@ pub fn big_uint_to_fp(big_uint: &BigUint) -> Fp {
 pub fn fp_to_big_uint(f: Fp) -> BigUint {
     BigUint::from_bytes_le(f.to_bytes().as_slice())
 }
+
+/// Calculates the maximum value that the Merkle Root can have, given N_BYTES and LEVELS
+pub fn calculate_max_root_balance(n_bytes: usize, n_levels: usize) -> BigUint {
+    // The max value that can be stored in a leaf node or a sibling node, according to the constraint set in the circuit
+    let max_leaf_value = BigUint::from(2u32).pow(n_bytes as u32 * 8) - 1u32;
+    max_leaf_value * (n_levels + 1)
+}
+
+/// Returns the bit length of the largest balance of `entries`, across all currencies. It is 0 if all the balances are 0.
+pub fn max_balance_bits<const N_CURRENCIES: usize>(entries: &[Entry<N_CURRENCIES>]) -> usize {
+    entries
+        .iter()
+        .flat_map(|entry| entry.balances().iter())
+        .map(|balance| balance.bits() as usize)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Returns the smallest N_BYTES such that every individual balance of `entries` lies within the range checked by the circuit
+pub fn min_n_bytes_for_entries<const N_CURRENCIES: usize>(
+    entries: &[Entry<N_CURRENCIES>],
+) -> usize {
+    let max_bits = max_balance_bits(entries);
+
+    // At least one byte is needed for the range check
+    std::cmp::max(1, (max_bits + 7) / 8)
+}
+
+/// Returns the smallest N_BYTES such that the maximum root balance given by `calculate_max_root_balance` covers the total balance of `entries` for every currency.
+/// The number of levels is the depth of the tree built from `entries`.
+pub fn min_n_bytes_for_root<const N_CURRENCIES: usize>(entries: &[Entry<N_CURRENCIES>]) -> usize {
+    let n_levels = (entries.len() as f64).log2().ceil() as usize;
+
+    let max_total = (0..N_CURRENCIES)
+        .map(|currency| {
+            entries
+                .iter()
+                .map(|entry| &entry.balances()[currency])
+                .sum::<BigUint>()
+        })
+        .max()
+        .unwrap_or_default();
+
+    let mut n_bytes = 1;
+    while calculate_max_root_balance(n_bytes, n_levels) < max_total {
+        n_bytes += 1;
+    }
+    n_bytes
+}
+
+/// Computes the single value that an exchange publishes to reference the liabilities commitment of a snapshot.
+/// The digest is equal to `H(root.hash, root.balance[0], root.balance[1], ..., root.balance[N_CURRENCIES - 1], timestamp)`
+pub fn commitment_digest<const N_CURRENCIES: usize>(root: &Node<N_CURRENCIES>, timestamp: u64) -> Fp
+where
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    let mut hash_preimage = [Fp::zero(); N_CURRENCIES + 2];
+    hash_preimage[0] = root.hash;
+    hash_preimage[1..=N_CURRENCIES].copy_from_slice(&root.balances);
+    hash_preimage[N_CURRENCIES + 1] = Fp::from(timestamp);
+
+    poseidon::Hash::<Fp, PoseidonSpec, ConstantLength<{ N_CURRENCIES + 2 }>, 2, 1>::init()
+        .hash(hash_preimage)
+}
+
+/// Recomputes the root of a merkle sum tree from its two children, namely the nodes of the penultimate level.
+///
+/// This allows to independently verify that published penultimate nodes hash to the committed root.
+pub fn root_from_penultimate<const N_CURRENCIES: usize>(
+    left: &Node<N_CURRENCIES>,
+    right: &Node<N_CURRENCIES>,
+) -> Node<N_CURRENCIES>
+where
+    [(); N_CURRENCIES + 2]: Sized,
+{
+    Node::middle(left, right)
+}
+
+/// Checks off-chain that the liabilities of the tree are covered by the declared assets, before proving anything.
+///
+/// Returns the indices of the currencies whose root balance exceeds the declared sum in `asset_sums`.
+pub fn check_solvency<const N_CURRENCIES: usize>(
+    tree: &(impl Tree<N_CURRENCIES> + ?Sized),
+    asset_sums: &[BigUint; N_CURRENCIES],
+) -> Result<(), Vec<usize>> {
+    let insolvent_currencies: Vec<usize> = tree
+        .root()
+        .balances
+        .iter()
+        .zip(asset_sums.iter())
+        .enumerate()
+        .filter(|(_, (root_balance, asset_sum))| fp_to_big_uint(**root_balance) > **asset_sum)
+        .map(|(currency, _)| currency)
+        .collect();
+
+    if insolvent_currencies.is_empty() {
+        Ok(())
+    } else {
+        Err(insolvent_currencies)
+    }
+}
+
+/// Returns true if the declared assets cover the liabilities of the tree for every currency, as a fast pre-flight check before proving.
+///
+/// See `check_solvency` to get the insolvent currencies.
+pub fn is_solvent<const N_CURRENCIES: usize>(
+    tree: &(impl Tree<N_CURRENCIES> + ?Sized),
+    asset_sums: &[BigUint; N_CURRENCIES],
+) -> bool {
+    check_solvency(tree, asset_sums).is_ok()
+}
+
+/// Returns, for each currency, the difference `asset_sum - root_balance` between the declared assets and the liabilities of the tree.
+///
+/// A negative margin means that the currency is insolvent. Returns `None` if any margin doesn't fit in an `i128`, which happens once the root balances or the asset sums exceed 127 bits, e.g. for trees with `N_BYTES` of 16 or more.
+pub fn solvency_margin<const N_CURRENCIES: usize>(
+    tree: &(impl Tree<N_CURRENCIES> + ?Sized),
+    asset_sums: &[BigUint; N_CURRENCIES],
+) -> Option<[i128; N_CURRENCIES]> {
+    let mut margins = [0i128; N_CURRENCIES];
+    for (currency, margin) in margins.iter_mut().enumerate() {
+        let liabilities = BigInt::from(fp_to_big_uint(tree.root().balances[currency]));
+        *margin = i128::try_from(BigInt::from(asset_sums[currency].clone()) - liabilities).ok()?;
+    }
+
+    Some(margins)
+}
@@ -0,0 +1,63 @@
+use crate::merkle_sum_tree::{EmptyEntries, Node};
+
+/// Computes the root of a Merkle Sum Tree from a stream of leaves, without materializing the levels of the tree.
+///
+/// Two nodes of the same level are combined into their parent as soon as both are known, so that at most one pending node per level is kept in memory.
+/// This is a memory efficient alternative to `build_merkle_tree_from_leaves` when no proof has to be generated from the tree.
+#[derive(Debug, Clone, Default)]
+pub struct RootAccumulator<const N_CURRENCIES: usize> {
+    // The node waiting for its sibling at each level, if any
+    pending: Vec<Option<Node<N_CURRENCIES>>>,
+    leaf_count: usize,
+}
+
+impl<const N_CURRENCIES: usize> RootAccumulator<N_CURRENCIES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    pub fn new() -> Self {
+        RootAccumulator {
+            pending: vec![],
+            leaf_count: 0,
+        }
+    }
+
+    /// Adds the next leaf of the tree
+    pub fn push(&mut self, leaf: Node<N_CURRENCIES>) {
+        let mut node = leaf;
+        let mut level = 0;
+
+        while let Some(left_sibling) = self.pending.get_mut(level).and_then(Option::take) {
+            node = Node::middle(&left_sibling, &node);
+            level += 1;
+        }
+
+        if level == self.pending.len() {
+            self.pending.push(None);
+        }
+        self.pending[level] = Some(node);
+        self.leaf_count += 1;
+    }
+
+    /// Returns the number of leaves pushed so far
+    pub fn leaf_count(&self) -> usize {
+        self.leaf_count
+    }
+
+    /// Returns the root of the tree.
+    ///
+    /// Returns an error if no leaf was pushed or if the number of leaves is not a power of 2. The leaves should be padded with zero entries beforehand, as done by `MerkleSumTree::from_entries`.
+    pub fn finalize(mut self) -> Result<Node<N_CURRENCIES>, Box<dyn std::error::Error>> {
+        if self.leaf_count == 0 {
+            return Err(Box::new(EmptyEntries));
+        }
+
+        if !self.leaf_count.is_power_of_two() {
+            return Err("The number of leaves must be a power of 2".into());
+        }
+
+        // With a power of 2 number of leaves, the root is the only pending node
+        Ok(self.pending.pop().flatten().unwrap())
+    }
+}